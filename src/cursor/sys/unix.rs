@@ -1,19 +1,17 @@
-use std::{
-    io::{self, Error, ErrorKind, Write},
-    time::Duration,
-};
+use std::io::{self, Error, ErrorKind};
 
 use crate::{
-    event::{filter::CursorPositionFilter, poll_internal, read_internal, InternalEvent},
-    terminal::{disable_raw_mode, enable_raw_mode, sys::is_raw_mode_enabled},
+    event::{filter::CursorPositionFilter, query, InternalEvent},
+    terminal::{disable_raw_mode, enable_raw_mode, query_timeout, sys::is_raw_mode_enabled},
 };
 
 /// Returns the cursor position (column, row).
 ///
 /// The top left cell is represented as `(0, 0)`.
 ///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 pub fn position() -> io::Result<(u16, u16)> {
     if is_raw_mode_enabled() {
         read_position_raw()
@@ -31,26 +29,18 @@ fn read_position() -> io::Result<(u16, u16)> {
 
 fn read_position_raw() -> io::Result<(u16, u16)> {
     // Use `ESC [ 6 n` to and retrieve the cursor position.
-    let mut stdout = io::stdout();
-    stdout.write_all(b"\x1B[6n")?;
-    stdout.flush()?;
-
-    loop {
-        match poll_internal(Some(Duration::from_millis(2000)), &CursorPositionFilter) {
-            Ok(true) => {
-                if let Ok(InternalEvent::CursorPosition(x, y)) =
-                    read_internal(&CursorPositionFilter)
-                {
-                    return Ok((x, y));
-                }
-            }
-            Ok(false) => {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "The cursor position could not be read within a normal duration",
-                ));
-            }
-            Err(_) => {}
-        }
+    let responses = query::send(
+        b"\x1B[6n",
+        CursorPositionFilter,
+        false,
+        false,
+        query_timeout(),
+    )?;
+    match responses.first() {
+        Some(InternalEvent::CursorPosition(x, y)) => Ok((*x, *y)),
+        _ => Err(Error::new(
+            ErrorKind::Other,
+            "The cursor position could not be read",
+        )),
     }
 }
@@ -55,6 +55,9 @@
 //!             Event::Paste(data) => println!("{:?}", data),
 //!             Event::Resize(width, height) => println!("New size {}x{}", width, height),
 //!             Event::ThemeModeChanged(mode) => println!("New theme mode {:?}", mode),
+//!             Event::Unknown(sequence) => println!("Unknown {:?}", sequence.bytes()),
+//!             // Events which need to be opted into, such as streamed pastes.
+//!             _ => {}
 //!         }
 //!     }
 //!     execute!(
@@ -102,6 +105,9 @@
 //!                 Event::Paste(data) => println!("Pasted {:?}", data),
 //!                 Event::Resize(width, height) => println!("New size {}x{}", width, height),
 //!                 Event::ThemeModeChanged(mode) => println!("New theme mode {:?}", mode),
+//!                 Event::Unknown(sequence) => println!("Unknown {:?}", sequence.bytes()),
+//!                 // Events which need to be opted into, such as streamed pastes.
+//!                 _ => {}
 //!             }
 //!         } else {
 //!             // Timeout expired and no `Event` is available
@@ -121,6 +127,11 @@
 //! them (`event-*`).
 
 pub(crate) mod filter;
+pub(crate) mod gesture;
+pub(crate) mod keymap;
+pub(crate) mod notation;
+#[cfg(unix)]
+pub(crate) mod query;
 pub(crate) mod read;
 pub(crate) mod source;
 #[cfg(feature = "event-stream")]
@@ -130,17 +141,23 @@ pub(crate) mod timeout;
 
 #[cfg(feature = "derive-more")]
 use derive_more::derive::IsVariant;
+pub use gesture::{MouseGesture, MouseGestureRecognizer};
+pub use keymap::{KeyMatch, KeyMatcher};
+#[cfg(feature = "serde")]
+pub use notation::key_notation;
+pub use notation::ParseKeyError;
 #[cfg(feature = "event-stream")]
-pub use stream::EventStream;
+pub use stream::{query_async, EventStream, QueryFuture};
 
 use crate::event::{
     filter::{EventFilter, Filter},
     read::InternalEventReader,
     timeout::PollTimeout,
 };
-use crate::{csi, Command};
+use crate::{csi, style::Color, terminal::WindowSize, Command};
 use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
 use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use bitflags::bitflags;
@@ -150,12 +167,32 @@ use std::hash::{Hash, Hasher};
 /// This needs to be static because there can be one event reader.
 static INTERNAL_EVENT_READER: Mutex<Option<InternalEventReader>> = parking_lot::const_mutex(None);
 
+/// How long to wait for the rest of an incomplete escape sequence, see [`set_escape_timeout`].
+static ESCAPE_TIMEOUT: Mutex<Option<Duration>> = parking_lot::const_mutex(None);
+
+/// Whether unknown sequences are reported, see [`set_report_unknown_sequences`].
+static REPORT_UNKNOWN_SEQUENCES: AtomicBool = AtomicBool::new(false);
+
+/// The mouse encoding the terminal uses, see [`set_mouse_encoding`].
+static MOUSE_ENCODING: Mutex<MouseEncoding> = parking_lot::const_mutex(MouseEncoding::Sgr);
+
+/// How long resize events are held back, see [`set_resize_coalescing`].
+static RESIZE_COALESCING: Mutex<Option<Duration>> = parking_lot::const_mutex(None);
+
+/// How bracketed pastes are reported, see [`set_paste_options`].
+#[cfg(feature = "bracketed-paste")]
+static PASTE_OPTIONS: Mutex<PasteOptions> = parking_lot::const_mutex(PasteOptions {
+    streaming: false,
+    raw_bytes: false,
+    max_size: None,
+});
+
 pub(crate) fn lock_internal_event_reader() -> MappedMutexGuard<'static, InternalEventReader> {
     MutexGuard::map(INTERNAL_EVENT_READER.lock(), |reader| {
         reader.get_or_insert_with(InternalEventReader::default)
     })
 }
-fn try_lock_internal_event_reader_for(
+pub(crate) fn try_lock_internal_event_reader_for(
     duration: Duration,
 ) -> Option<MappedMutexGuard<'static, InternalEventReader>> {
     Some(MutexGuard::map(
@@ -256,6 +293,224 @@ pub fn read() -> std::io::Result<Event> {
     }
 }
 
+/// Sets how long to wait for the rest of an escape sequence before treating the input
+/// received so far as individual keys.
+///
+/// A lone `Esc` byte can either be the Escape key or the start of an escape sequence. With a
+/// timeout set, an incomplete sequence is held back until either the rest of it arrives or the
+/// timeout elapses, in which case it is reported as [`KeyCode::Esc`] (or as an `Alt` modified
+/// key for `Esc [` and `Esc O`). This is similar to vim's `ttimeoutlen` and makes escape
+/// sequences which are split across reads, for example over slow SSH connections, reliable.
+///
+/// With `None` (the default) an `Esc` is reported as soon as it is the last byte of a read.
+///
+/// This has no effect on Windows. The timeout can be changed while another thread is blocked in
+/// [`read`] or [`poll`], and applies to the input read from then on.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// crossterm::event::set_escape_timeout(Some(Duration::from_millis(50)));
+/// ```
+pub fn set_escape_timeout(timeout: Option<Duration>) {
+    *ESCAPE_TIMEOUT.lock() = timeout;
+}
+
+#[cfg(unix)]
+pub(crate) fn escape_timeout() -> Option<Duration> {
+    *ESCAPE_TIMEOUT.lock()
+}
+
+/// Sets whether input sequences crossterm doesn't recognize are reported as [`Event::Unknown`].
+///
+/// By default, such sequences are silently dropped. Enabling this makes it possible to handle or
+/// log what unusual terminals send. This includes OSC, DCS and APC strings which don't answer a
+/// query, such as late responses to a query which timed out, with the string terminator `ESC \`.
+///
+/// This has no effect on Windows. It can be changed while another thread is blocked in [`read`]
+/// or [`poll`], and applies to the input read from then on.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{read, set_report_unknown_sequences, Event};
+///
+/// set_report_unknown_sequences(true);
+/// if let Event::Unknown(sequence) = read()? {
+///     eprintln!("unknown sequence: {:?}", sequence.bytes());
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub fn set_report_unknown_sequences(enabled: bool) {
+    REPORT_UNKNOWN_SEQUENCES.store(enabled, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+pub(crate) fn report_unknown_sequences() -> bool {
+    REPORT_UNKNOWN_SEQUENCES.load(Ordering::Relaxed)
+}
+
+/// Tells the input parser which mouse encoding was enabled.
+///
+/// Some encodings can't be told apart by their sequences alone: [`MouseEncoding::Utf8`] looks
+/// like [`MouseEncoding::X10`] and [`MouseEncoding::SgrPixels`] looks like [`MouseEncoding::Sgr`].
+/// Set the encoding along with [`EnableMouseCaptureMode`] so that the positions of mouse events are
+/// decoded correctly. The other encodings are always decoded, so the default of
+/// [`MouseEncoding::Sgr`] works for [`EnableMouseCapture`]. Set [`MouseEncoding::SgrPixels`] along
+/// with [`EnableMousePixelPositions`] as well.
+///
+/// This has no effect on Windows. The encoding can be changed while another thread is blocked in
+/// [`read`] or [`poll`], and applies to the input read from then on.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::stdout;
+///
+/// use crossterm::event::{
+///     set_mouse_encoding, EnableMouseCaptureMode, MouseCaptureMode, MouseEncoding, MouseTracking,
+/// };
+/// use crossterm::execute;
+///
+/// let mode = MouseCaptureMode {
+///     tracking: MouseTracking::ButtonDrag,
+///     encoding: MouseEncoding::SgrPixels,
+/// };
+/// execute!(stdout(), EnableMouseCaptureMode(mode))?;
+/// set_mouse_encoding(mode.encoding);
+/// # std::io::Result::Ok(())
+/// ```
+pub fn set_mouse_encoding(encoding: MouseEncoding) {
+    *MOUSE_ENCODING.lock() = encoding;
+}
+
+#[cfg(unix)]
+pub(crate) fn mouse_encoding() -> MouseEncoding {
+    *MOUSE_ENCODING.lock()
+}
+
+/// Reports the given Unix signals as [`Event::Signal`], in order with the other events, so that
+/// they can be handled in the event loop.
+///
+/// This installs handlers for the signals, which replace their default action. For example, the
+/// process no longer terminates on `SIGTERM` by itself. Signals can't be unregistered.
+/// `SIGWINCH` is always reported as [`Event::Resize`], `SIGCONT` is reported as
+/// [`Event::Resumed`] once registered, and `SIGKILL`, `SIGSTOP`, `SIGILL`, `SIGFPE` and `SIGSEGV`
+/// can't be registered. Note that the terminal only sends `SIGINT` for `Ctrl+C` if raw mode is
+/// disabled.
+///
+/// The signals can be registered while another thread is blocked in [`read`] or [`poll`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{read, register_signals, Event};
+/// use signal_hook::consts::{SIGHUP, SIGTERM};
+///
+/// register_signals(&[SIGTERM, SIGHUP])?;
+/// loop {
+///     if let Event::Signal(SIGTERM | SIGHUP) = read()? {
+///         break;
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(unix)]
+pub fn register_signals(signals: &[i32]) -> std::io::Result<()> {
+    // The signals are registered with the event source, which is created along with the reader.
+    if !source::unix::has_signal_handle() {
+        drop(lock_internal_event_reader());
+    }
+    source::unix::register_signals(signals)
+}
+
+/// Sets whether resize events are collapsed to the latest one, so that resizing a window, which
+/// usually causes a batch of resize events, results in a single redraw.
+///
+/// With `Some(debounce)`, a resize event is held back until no other resize followed it for
+/// `debounce`, and is then reported as it was read, as [`Event::Resize`], or as
+/// [`Event::WindowResize`] for in-band resizes. A zero debounce only collapses the resize events which are already queued. Any other event
+/// reports a held back resize first, so events stay in order.
+///
+/// With `None` (the default), every resize is reported as it happens.
+///
+/// The debounce can be changed while another thread is blocked in [`read`] or [`poll`], and
+/// applies to the resizes read from then on. A held back resize is reported once coalescing is
+/// disabled.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use crossterm::event::{read, set_resize_coalescing, Event};
+///
+/// set_resize_coalescing(Some(Duration::from_millis(30)));
+/// loop {
+///     if let Event::Resize(columns, rows) = read()? {
+///         println!("Resized to {}x{} cells", columns, rows);
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub fn set_resize_coalescing(debounce: Option<Duration>) {
+    *RESIZE_COALESCING.lock() = debounce;
+}
+
+/// Sets how bracketed pastes are reported, see [`PasteOptions`].
+///
+/// This has no effect on Windows. The options can be changed while another thread is blocked in
+/// [`read`] or [`poll`], and apply to the input read from then on.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{read, set_paste_options, Event, PasteOptions};
+///
+/// set_paste_options(PasteOptions {
+///     streaming: true,
+///     max_size: Some(16 * 1024 * 1024),
+///     ..PasteOptions::default()
+/// });
+/// loop {
+///     match read()? {
+///         Event::PasteStart => println!("Pasting..."),
+///         Event::PasteChunk(chunk) => println!("Received {} bytes", chunk.len()),
+///         Event::PasteEnd { truncated: true } => println!("The paste was too large"),
+///         _ => {}
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "bracketed-paste")]
+pub fn set_paste_options(options: PasteOptions) {
+    *PASTE_OPTIONS.lock() = options;
+}
+
+#[cfg(all(unix, feature = "bracketed-paste"))]
+pub(crate) fn paste_options() -> PasteOptions {
+    *PASTE_OPTIONS.lock()
+}
+
+/// Options for reporting bracketed pastes, set with [`set_paste_options`].
+///
+/// By default, a paste is reported as a single [`Event::Paste`] once it's complete, with any
+/// invalid UTF-8 replaced by `U+FFFD`.
+#[cfg(feature = "bracketed-paste")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PasteOptions {
+    /// Reports a paste as it arrives, as [`Event::PasteStart`], any number of
+    /// [`Event::PasteChunk`]s and [`Event::PasteEnd`], so that the paste isn't held in memory.
+    pub streaming: bool,
+    /// Reports the pasted bytes as they are, as [`Event::PasteBytes`] or [`PasteData::Bytes`]
+    /// chunks, instead of converting them to text.
+    pub raw_bytes: bool,
+    /// The maximum number of bytes of a paste. Bytes beyond the maximum are dropped.
+    pub max_size: Option<usize>,
+}
+
 /// Polls to check if there are any `InternalEvent`s that can be read within the given duration.
 pub(crate) fn poll_internal<F>(timeout: Option<Duration>, filter: &F) -> std::io::Result<bool>
 where
@@ -287,8 +542,6 @@ bitflags! {
     /// Represents special flags that tell compatible terminals to add extra information to keyboard events.
     ///
     /// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/#progressive-enhancement> for more information.
-    ///
-    /// Alternate keys and Unicode codepoints are not yet supported by crossterm.
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
     #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
     pub struct KeyboardEnhancementFlags: u8 {
@@ -299,23 +552,23 @@ bitflags! {
         /// [`KeyEventKind::Release`] when keys are autorepeated or released.
         const REPORT_EVENT_TYPES = 0b0000_0010;
         /// Send [alternate keycodes](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#key-codes)
-        /// in addition to the base keycode. The alternate keycode overrides the base keycode in
-        /// resulting `KeyEvent`s.
+        /// in addition to the base keycode. The shifted keycode overrides the base keycode in
+        /// resulting `KeyEvent`s, all of them are available in [`KeyEvent.alternate_keys`].
         const REPORT_ALTERNATE_KEYS = 0b0000_0100;
         /// Represent all keyboard events as CSI-u sequences. This is required to get repeat/release
         /// events for plain-text keys.
         const REPORT_ALL_KEYS_AS_ESCAPE_CODES = 0b0000_1000;
-        // Send the Unicode codepoint as well as the keycode.
-        //
-        // *Note*: this is not yet supported by crossterm.
-        // const REPORT_ASSOCIATED_TEXT = 0b0001_0000;
+        /// Send the text a key produces as well as the keycode, which is set as
+        /// [`KeyEvent.text`]. Only has an effect together with
+        /// [`KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES`].
+        const REPORT_ASSOCIATED_TEXT = 0b0001_0000;
     }
 }
 
 /// The terminal's current mode of the synchronized output feature.
 ///
 /// <https://gist.github.com/christianparpart/d8a62cc1ab659194337d73e399004036#feature-detection>
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, Default, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SynchronizedOutputMode {
     /// Screen updates are not shown to the user until mode is disabled.
@@ -323,15 +576,10 @@ pub enum SynchronizedOutputMode {
     /// Screen updates are shown as usual (e.g. as soon as they arrive).
     Reset,
     /// The terminal does not support synchronized output sequences.
+    #[default]
     NotSupported,
 }
 
-impl Default for SynchronizedOutputMode {
-    fn default() -> Self {
-        Self::NotSupported
-    }
-}
-
 /// A command that enables mouse event capturing.
 ///
 /// Mouse events can be captured with [read](./fn.read.html)/[poll](./fn.poll.html).
@@ -396,6 +644,201 @@ impl Command for DisableMouseCapture {
     }
 }
 
+/// How much mouse activity is reported, see [`MouseCaptureMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseTracking {
+    /// Report button presses and releases, and scrolling (`?1000h`).
+    Click,
+    /// Also report motion while a button is pressed (`?1002h`).
+    ButtonDrag,
+    /// Also report all motion, even without a pressed button (`?1003h`).
+    AnyMotion,
+}
+
+impl MouseTracking {
+    // The modes to enable, in order. The lower levels are enabled as well for terminals which
+    // don't support the higher ones.
+    fn modes(self) -> &'static [u16] {
+        match self {
+            MouseTracking::Click => &[1000],
+            MouseTracking::ButtonDrag => &[1000, 1002],
+            MouseTracking::AnyMotion => &[1000, 1002, 1003],
+        }
+    }
+}
+
+/// How the terminal encodes mouse events, see [`MouseCaptureMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseEncoding {
+    /// The default encoding, limited to positions up to 223.
+    X10,
+    /// UTF-8 encoded positions, up to 2015 (`?1005h`).
+    Utf8,
+    /// urxvt's decimal encoding (`?1015h`).
+    Urxvt,
+    /// SGR's decimal encoding, which also reports which button was released (`?1006h`).
+    Sgr,
+    /// Like [`MouseEncoding::Sgr`], but with positions in pixels (`?1016h`).
+    SgrPixels,
+}
+
+impl MouseEncoding {
+    fn mode(self) -> Option<u16> {
+        match self {
+            MouseEncoding::X10 => None,
+            MouseEncoding::Utf8 => Some(1005),
+            MouseEncoding::Urxvt => Some(1015),
+            MouseEncoding::Sgr => Some(1006),
+            MouseEncoding::SgrPixels => Some(1016),
+        }
+    }
+}
+
+/// The mouse tracking level and encoding enabled by [`EnableMouseCaptureMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseCaptureMode {
+    /// How much mouse activity is reported.
+    pub tracking: MouseTracking,
+    /// How mouse events are encoded.
+    pub encoding: MouseEncoding,
+}
+
+/// A command that enables mouse event capturing with the given tracking level and encoding.
+///
+/// Unlike [`EnableMouseCapture`], which enables all of them, this only enables what's needed,
+/// for example to avoid the flood of events of [`MouseTracking::AnyMotion`] if only clicks and
+/// drags are of interest. Like [`EnableMousePixelPositions`], writing the command doesn't change
+/// how the input is decoded, so call [`set_mouse_encoding`] with the encoding as well.
+///
+/// It should be paired with [`DisableMouseCaptureMode`] with the same mode at the end of
+/// execution.
+///
+/// On Windows, this is the same as [`EnableMouseCapture`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableMouseCaptureMode(pub MouseCaptureMode);
+
+impl Command for EnableMouseCaptureMode {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        for mode in self.0.tracking.modes() {
+            write!(f, csi!("?{}h"), mode)?;
+        }
+        if let Some(mode) = self.0.encoding.mode() {
+            write!(f, csi!("?{}h"), mode)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        sys::windows::enable_mouse_capture()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        false
+    }
+}
+
+/// A command that disables the mouse event capturing enabled by [`EnableMouseCaptureMode`].
+///
+/// Only the modes enabled for the given mode are disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableMouseCaptureMode(pub MouseCaptureMode);
+
+impl Command for DisableMouseCaptureMode {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // The inverse commands of EnableMouseCaptureMode, in reverse order.
+        if let Some(mode) = self.0.encoding.mode() {
+            write!(f, csi!("?{}l"), mode)?;
+        }
+        for mode in self.0.tracking.modes().iter().rev() {
+            write!(f, csi!("?{}l"), mode)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        sys::windows::disable_mouse_capture()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        false
+    }
+}
+
+/// A command that makes the terminal report mouse positions in pixels instead of cells
+/// (SGR-Pixels mode, `?1016h`).
+///
+/// This only changes the encoding of mouse events, mouse capture still needs to be enabled with
+/// [`EnableMouseCapture`]. Since pixel positions are reported with the same sequences as cell
+/// positions, call [`set_mouse_encoding`] with [`MouseEncoding::SgrPixels`] as well, so that
+/// events are marked with [`MousePositionUnit::Pixel`].
+///
+/// It should be paired with [`DisableMousePixelPositions`] at the end of execution.
+///
+/// This has no effect on Windows.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::stdout;
+///
+/// use crossterm::event::{
+///     set_mouse_encoding, EnableMouseCapture, EnableMousePixelPositions, MouseEncoding,
+/// };
+/// use crossterm::execute;
+///
+/// execute!(stdout(), EnableMouseCapture, EnableMousePixelPositions)?;
+/// set_mouse_encoding(MouseEncoding::SgrPixels);
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableMousePixelPositions;
+
+impl Command for EnableMousePixelPositions {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?1016h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that makes the terminal report mouse positions in cells again.
+///
+/// Turning off SGR-Pixels mode resets the terminal to the default mouse encoding, so execute
+/// [`EnableMouseCapture`] again if mouse capture stays enabled, and call [`set_mouse_encoding`]
+/// with [`MouseEncoding::Sgr`].
+///
+/// See [`EnableMousePixelPositions`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableMousePixelPositions;
+
+impl Command for DisableMousePixelPositions {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?1016l"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
 /// A command that enables focus event emission.
 ///
 /// It should be paired with [`DisableFocusChange`] at the end of execution.
@@ -502,21 +945,247 @@ impl Command for DisableBracketedPaste {
 /// execute!(stdout, PopKeyboardEnhancementFlags);
 /// ```
 ///
-/// Note that, currently, only the following support this protocol:
-/// * [kitty terminal](https://sw.kovidgoyal.net/kitty/)
-/// * [foot terminal](https://codeberg.org/dnkl/foot/issues/319)
-/// * [WezTerm terminal](https://wezfurlong.org/wezterm/config/lua/config/enable_kitty_keyboard.html)
-/// * [alacritty terminal](https://github.com/alacritty/alacritty/issues/6378)
-/// * [notcurses library](https://github.com/dankamongmen/notcurses/issues/2131)
-/// * [neovim text editor](https://github.com/neovim/neovim/pull/18181)
-/// * [kakoune text editor](https://github.com/mawww/kakoune/issues/4103)
-/// * [dte text editor](https://gitlab.com/craigbarnes/dte/-/issues/138)
+/// Note that, currently, only the following support this protocol:
+/// * [kitty terminal](https://sw.kovidgoyal.net/kitty/)
+/// * [foot terminal](https://codeberg.org/dnkl/foot/issues/319)
+/// * [WezTerm terminal](https://wezfurlong.org/wezterm/config/lua/config/enable_kitty_keyboard.html)
+/// * [alacritty terminal](https://github.com/alacritty/alacritty/issues/6378)
+/// * [notcurses library](https://github.com/dankamongmen/notcurses/issues/2131)
+/// * [neovim text editor](https://github.com/neovim/neovim/pull/18181)
+/// * [kakoune text editor](https://github.com/mawww/kakoune/issues/4103)
+/// * [dte text editor](https://gitlab.com/craigbarnes/dte/-/issues/138)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PushKeyboardEnhancementFlags(pub KeyboardEnhancementFlags);
+
+impl Command for PushKeyboardEnhancementFlags {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "{}{}u", csi!(">"), self.0.bits())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that disables extra kinds of keyboard events.
+///
+/// Specifically, it pops one level of keyboard enhancement flags.
+///
+/// See [`PushKeyboardEnhancementFlags`] and <https://sw.kovidgoyal.net/kitty/keyboard-protocol/> for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PopKeyboardEnhancementFlags;
+
+impl Command for PopKeyboardEnhancementFlags {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("<1u"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that enables xterm's [modifyOtherKeys] mode, so that keys like `Ctrl+Shift+A` or
+/// `Ctrl+Enter` can be told apart from their unmodified counterparts.
+///
+/// This is an alternative to [`PushKeyboardEnhancementFlags`] for terminals (and multiplexers
+/// like tmux with `extended-keys`) which don't implement the kitty keyboard protocol. Both the
+/// `CSI 27 ; modifiers ; code ~` and the `formatOtherKeys` `CSI code ; modifiers u` encodings
+/// are parsed.
+///
+/// It should be paired with [`DisableModifyOtherKeys`] at the end of execution.
+///
+/// [modifyOtherKeys]: https://invisible-island.net/xterm/manpage/xterm.html#VT100-Widget-Resources:modifyOtherKeys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableModifyOtherKeys;
+
+impl Command for EnableModifyOtherKeys {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!(">4;2m"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that disables xterm's modifyOtherKeys mode.
+///
+/// See [`EnableModifyOtherKeys`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableModifyOtherKeys;
+
+impl Command for DisableModifyOtherKeys {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!(">4;0m"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that enables the [win32-input-mode] of Windows Terminal and ConPTY, which reports
+/// every key press and release as a Win32 key event record
+/// (`CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _`).
+///
+/// This gives applications on Unix, for example connected over SSH from Windows Terminal,
+/// [`KeyEventKind::Release`] and [`KeyEventKind::Repeat`] events and unambiguous keys without
+/// the kitty keyboard protocol. Modifier keys are reported as [`KeyCode::Modifier`] and keypad
+/// keys have [`KeyEventState::KEYPAD`] set.
+///
+/// It should be paired with [`DisableWin32InputMode`] at the end of execution.
+///
+/// [win32-input-mode]: https://github.com/microsoft/terminal/blob/main/doc/specs/%234999%20-%20Improved%20keyboard%20handling%20in%20Conpty.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableWin32InputMode;
+
+impl Command for EnableWin32InputMode {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?9001h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that disables the win32-input-mode.
+///
+/// See [`EnableWin32InputMode`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableWin32InputMode;
+
+impl Command for DisableWin32InputMode {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?9001l"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that enables in-band resize notifications (mode 2048), which makes the terminal
+/// report its new size in cells and pixels in the input (`CSI 48 ; rows ; columns ; height ;
+/// width t`) whenever it changes. Kitty, Ghostty, foot and others support it.
+///
+/// The reports are read as [`Event::WindowResize`]. Unlike `SIGWINCH`, they work through
+/// multiplexers and SSH without querying the size, and carry the size in pixels. Since terminals
+/// send both, a resize to the same size is only reported once, as [`Event::WindowResize`] or as
+/// [`Event::Resize`] for `SIGWINCH`, whichever arrives first.
+///
+/// It should be paired with [`DisableInBandResize`] at the end of execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableInBandResize;
+
+impl Command for EnableInBandResize {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?2048h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that disables in-band resize notifications.
+///
+/// See [`EnableInBandResize`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableInBandResize;
+
+impl Command for DisableInBandResize {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?2048l"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that enables application cursor keys mode (DECCKM), in which the terminal sends the
+/// arrow keys, `Home` and `End` as `ESC O` sequences instead of `CSI` sequences. Both are read as
+/// the same keys.
+///
+/// It should be paired with [`DisableApplicationCursorKeys`] at the end of execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableApplicationCursorKeys;
+
+impl Command for EnableApplicationCursorKeys {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?1h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that disables application cursor keys mode (DECCKM).
+///
+/// See [`EnableApplicationCursorKeys`] for more information.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct PushKeyboardEnhancementFlags(pub KeyboardEnhancementFlags);
+pub struct DisableApplicationCursorKeys;
 
-impl Command for PushKeyboardEnhancementFlags {
+impl Command for DisableApplicationCursorKeys {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        write!(f, "{}{}u", csi!(">"), self.0.bits())
+        f.write_str(csi!("?1l"))
     }
 
     #[cfg(windows)]
@@ -530,17 +1199,43 @@ impl Command for PushKeyboardEnhancementFlags {
     }
 }
 
-/// A command that disables extra kinds of keyboard events.
+/// A command that enables application keypad mode (DECKPAM), in which the terminal sends the keys
+/// of the numeric keypad as `ESC O` sequences instead of the characters they produce.
 ///
-/// Specifically, it pops one level of keyboard enhancement flags.
+/// The keypad keys are then read with [`KeyEventState::KEYPAD`] set, so that, for example, the
+/// keypad's `Enter` and digits can be told apart from the main keys on terminals which don't
+/// support the [kitty keyboard protocol](PushKeyboardEnhancementFlags). Note that with `Num Lock`
+/// off, many terminals send the keypad keys as cursor keys instead.
 ///
-/// See [`PushKeyboardEnhancementFlags`] and <https://sw.kovidgoyal.net/kitty/keyboard-protocol/> for more information.
+/// It should be paired with [`DisableApplicationKeypad`] at the end of execution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct PopKeyboardEnhancementFlags;
+pub struct EnableApplicationKeypad;
 
-impl Command for PopKeyboardEnhancementFlags {
+impl Command for EnableApplicationKeypad {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(csi!("<1u"))
+        f.write_str("\x1B=")
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+/// A command that disables application keypad mode, which selects numeric keypad mode (DECKPNM).
+///
+/// See [`EnableApplicationKeypad`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableApplicationKeypad;
+
+impl Command for DisableApplicationKeypad {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1B>")
     }
 
     #[cfg(windows)]
@@ -616,10 +1311,44 @@ pub enum Event {
     /// enabled.
     #[cfg(feature = "bracketed-paste")]
     Paste(String),
+    /// The bytes that were pasted into the terminal. Emitted instead of [`Event::Paste`] if
+    /// [`PasteOptions::raw_bytes`] is set.
+    #[cfg(feature = "bracketed-paste")]
+    PasteBytes(Vec<u8>),
+    /// A paste started. Emitted if [`PasteOptions::streaming`] is set.
+    #[cfg(feature = "bracketed-paste")]
+    PasteStart,
+    /// A part of the content of a paste, in order. Emitted if [`PasteOptions::streaming`] is set.
+    #[cfg(feature = "bracketed-paste")]
+    PasteChunk(PasteData),
+    /// A paste ended. Emitted if [`PasteOptions::streaming`] is set.
+    #[cfg(feature = "bracketed-paste")]
+    PasteEnd {
+        /// Whether bytes beyond [`PasteOptions::max_size`] were dropped.
+        truncated: bool,
+    },
     /// An resize event with new dimensions after resize (columns, rows).
-    /// **Note** that resize events can occur in batches.
+    /// **Note** that resize events can occur in batches, see [`set_resize_coalescing`].
     Resize(u16, u16),
+    /// A resize event with the new size of the window in cells and pixels. Emitted instead of
+    /// [`Event::Resize`] for the in-band resize notifications enabled by [`EnableInBandResize`].
+    WindowResize(WindowSize),
     ThemeModeChanged(ThemeMode),
+    /// The process was continued after it had been stopped, for example by
+    /// [`suspend`](crate::terminal::suspend) or by job control. Only emitted on Unix, once
+    /// `SIGCONT` was registered with [`register_signals`] or by
+    /// [`suspend`](crate::terminal::suspend).
+    ///
+    /// The screen should be redrawn, since the shell has likely drawn over it. If the process was
+    /// stopped by something other than [`suspend`](crate::terminal::suspend), such as
+    /// `kill -STOP`, the terminal modes are left as the shell set them, so raw mode and the other
+    /// modes should be entered again as well. Disabling and enabling raw mode re-applies it.
+    Resumed,
+    /// A Unix signal registered with [`register_signals`] was received.
+    Signal(i32),
+    /// An input sequence crossterm doesn't recognize. Only emitted if reporting unknown sequences
+    /// has been enabled with [`set_report_unknown_sequences`].
+    Unknown(UnknownSequence),
 }
 
 impl Event {
@@ -791,7 +1520,8 @@ impl Event {
         }
     }
 
-    /// Returns the size as a tuple if the event is a resize event, otherwise `None`.
+    /// Returns the size as a tuple `(columns, rows)` if the event is a [`Event::Resize`] or
+    /// [`Event::WindowResize`] event, otherwise `None`.
     ///
     /// This is a convenience method that makes code which only cares about resize events easier to write.
     ///
@@ -809,11 +1539,161 @@ impl Event {
     pub fn as_resize_event(&self) -> Option<(u16, u16)> {
         match self {
             Event::Resize(columns, rows) => Some((*columns, *rows)),
+            Event::WindowResize(size) => Some((size.columns, size.rows)),
             _ => None,
         }
     }
 }
 
+/// A part of the content of a paste, see [`Event::PasteChunk`].
+#[cfg(feature = "bracketed-paste")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub enum PasteData {
+    /// Pasted text. A chunk never ends in the middle of a character.
+    Text(String),
+    /// Pasted bytes, if [`PasteOptions::raw_bytes`] is set.
+    Bytes(Vec<u8>),
+}
+
+#[cfg(feature = "bracketed-paste")]
+impl PasteData {
+    /// Returns the length of the chunk in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            PasteData::Text(text) => text.len(),
+            PasteData::Bytes(bytes) => bytes.len(),
+        }
+    }
+
+    /// Returns whether the chunk is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Represents an input sequence that couldn't be parsed into an [`Event`].
+///
+/// The bytes are stored inline, so that [`Event`] can be `Copy`. Of a sequence longer than
+/// [`UnknownSequence::CAPACITY`] bytes, only the start is kept.
+///
+/// See [`set_report_unknown_sequences`].
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SerializedUnknownSequence", from = "SerializedUnknownSequence")
+)]
+#[derive(PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct UnknownSequence {
+    // The first bytes of the sequence, followed by zeros.
+    bytes: [u8; UnknownSequence::CAPACITY],
+    len: u8,
+    truncated: bool,
+}
+
+impl UnknownSequence {
+    /// The maximum number of bytes kept of a sequence.
+    pub const CAPACITY: usize = 32;
+
+    pub(crate) fn new(sequence: &[u8]) -> UnknownSequence {
+        let len = sequence.len().min(UnknownSequence::CAPACITY);
+        let mut bytes = [0; UnknownSequence::CAPACITY];
+        bytes[..len].copy_from_slice(&sequence[..len]);
+        UnknownSequence {
+            bytes,
+            len: len as u8,
+            truncated: len < sequence.len(),
+        }
+    }
+
+    /// Returns the raw bytes of the sequence, including the leading `ESC`.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    /// Returns whether the sequence was longer than [`UnknownSequence::CAPACITY`] bytes, in which
+    /// case [`UnknownSequence::bytes`] only returns its start.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns the parsed sequence if it's a well-formed control sequence (`ESC [ ...`).
+    #[cfg(unix)]
+    pub fn csi(&self) -> Option<CsiSequence> {
+        if self.truncated {
+            return None;
+        }
+        sys::unix::parse::parse_csi_sequence(self.bytes())
+    }
+}
+
+impl fmt::Debug for UnknownSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnknownSequence")
+            .field("bytes", &self.bytes())
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedUnknownSequence {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+#[cfg(feature = "serde")]
+impl From<UnknownSequence> for SerializedUnknownSequence {
+    fn from(sequence: UnknownSequence) -> Self {
+        SerializedUnknownSequence {
+            bytes: sequence.bytes().to_vec(),
+            truncated: sequence.truncated,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedUnknownSequence> for UnknownSequence {
+    fn from(sequence: SerializedUnknownSequence) -> Self {
+        let mut unknown = UnknownSequence::new(&sequence.bytes);
+        unknown.truncated |= sequence.truncated;
+        unknown
+    }
+}
+
+/// Represents a control sequence (`ESC [ ...`) split into its parts.
+///
+/// See [ECMA-48](https://ecma-international.org/publications-and-standards/standards/ecma-48/)
+/// section 5.4 for the structure of control sequences.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct CsiSequence {
+    /// The private marker (`<`, `=`, `>` or `?`) preceding the parameters, if any.
+    pub private_marker: Option<u8>,
+    /// The `;` separated parameters, each a list of its `:` separated sub-parameters.
+    ///
+    /// Empty (sub-)parameters are reported as `0`.
+    pub parameters: Vec<Vec<u32>>,
+    /// The intermediate bytes (`0x20..=0x2F`) between the parameters and the final byte.
+    pub intermediates: Vec<u8>,
+    /// The final byte (`0x40..=0x7E`).
+    pub final_byte: u8,
+}
+
+/// A response of the terminal to a query, see [`query`](crate::terminal::query).
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum QueryResponse<'a> {
+    /// A control sequence (`ESC [ ...`).
+    Csi(&'a CsiSequence),
+    /// The data of an OSC (`ESC ]`) string, without the introducer and terminator.
+    Osc(&'a [u8]),
+    /// The data of a DCS (`ESC P`) string, without the introducer and terminator.
+    Dcs(&'a [u8]),
+    /// The data of an APC (`ESC _`) string, without the introducer and terminator.
+    Apc(&'a [u8]),
+}
+
 /// Represents a mouse event.
 ///
 /// # Platform-specific Notes
@@ -835,11 +1715,52 @@ pub struct MouseEvent {
     /// The kind of mouse event that was caused.
     pub kind: MouseEventKind,
     /// The column that the event occurred on.
+    ///
+    /// This is the x position in pixels if [`MouseEvent::position_unit`] is
+    /// [`MousePositionUnit::Pixel`].
     pub column: u16,
     /// The row that the event occurred on.
+    ///
+    /// This is the y position in pixels if [`MouseEvent::position_unit`] is
+    /// [`MousePositionUnit::Pixel`].
     pub row: u16,
     /// The key modifiers active when the event occurred.
     pub modifiers: KeyModifiers,
+    /// Whether `column` and `row` are cell or pixel positions.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub position_unit: MousePositionUnit,
+}
+
+impl MouseEvent {
+    /// Returns the (column, row) cell the event occurred on.
+    ///
+    /// Pixel positions are converted with the cell size derived from `window_size`, see
+    /// [`window_size`](crate::terminal::window_size). Returns `None` for pixel positions if the
+    /// size of the window in pixels is unknown.
+    pub fn cell_position(&self, window_size: &WindowSize) -> Option<(u16, u16)> {
+        match self.position_unit {
+            MousePositionUnit::Cell => Some((self.column, self.row)),
+            MousePositionUnit::Pixel => {
+                if window_size.width == 0 || window_size.height == 0 {
+                    return None;
+                }
+                let cell_width = (window_size.width / window_size.columns.max(1)).max(1);
+                let cell_height = (window_size.height / window_size.rows.max(1)).max(1);
+                Some((self.column / cell_width, self.row / cell_height))
+            }
+        }
+    }
+}
+
+/// The unit of the position of a [`MouseEvent`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Default, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MousePositionUnit {
+    /// The position is a cell (column and row).
+    #[default]
+    Cell,
+    /// The position is in pixels, reported in SGR-Pixels mode (see [`EnableMousePixelPositions`]).
+    Pixel,
 }
 
 /// A mouse event kind.
@@ -884,6 +1805,14 @@ pub enum MouseButton {
     Right,
     /// Middle mouse button.
     Middle,
+    /// Back mouse button (button 8), usually a thumb button.
+    Back,
+    /// Forward mouse button (button 9), usually a thumb button.
+    Forward,
+    /// Mouse button 10.
+    Button10,
+    /// Mouse button 11.
+    Button11,
 }
 
 bitflags! {
@@ -990,7 +1919,7 @@ bitflags! {
 
 /// Represents a key event.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, PartialOrd, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct KeyEvent {
     /// The key itself.
     pub code: KeyCode,
@@ -1007,6 +1936,128 @@ pub struct KeyEvent {
     /// Only set if [`KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES`] has been enabled with
     /// [`PushKeyboardEnhancementFlags`].
     pub state: KeyEventState,
+    /// The text the key produces, for example the result of a dead key or compose sequence.
+    ///
+    /// Only set if [`KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT`] has been enabled with
+    /// [`PushKeyboardEnhancementFlags`]. It's not taken into account when comparing or hashing
+    /// key events.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub text: Option<KeyText>,
+    /// The alternate keys reported for this key event.
+    ///
+    /// Only set if [`KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS`] has been enabled with
+    /// [`PushKeyboardEnhancementFlags`] and the terminal reported any. It's not taken into account
+    /// when comparing or hashing key events, see [`KeyEvent::unshifted`] and
+    /// [`KeyEvent::base_layout`] to match on the alternate keys instead.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub alternate_keys: Option<AlternateKeys>,
+}
+
+/// The text a key produces, see [`KeyEvent::text`].
+///
+/// The text is stored inline, so that [`KeyEvent`] can be `Copy`, and holds up to
+/// [`KeyText::CAPACITY`] bytes. It dereferences to a `str`.
+///
+/// # Examples
+///
+/// ```
+/// use crossterm::event::KeyText;
+///
+/// let text = KeyText::new("e\u{301}").unwrap();
+/// assert_eq!(text.as_str(), "e\u{301}");
+/// assert_eq!(text.chars().count(), 2);
+/// ```
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+#[derive(PartialOrd, PartialEq, Eq, Ord, Clone, Copy, Hash)]
+pub struct KeyText {
+    // The UTF-8 encoded text, followed by zeros.
+    bytes: [u8; KeyText::CAPACITY],
+    len: u8,
+}
+
+impl KeyText {
+    /// The maximum length of the text in bytes. Longer text isn't reported.
+    pub const CAPACITY: usize = 16;
+
+    /// Creates the text, or returns `None` if it's longer than [`KeyText::CAPACITY`] bytes.
+    pub fn new(text: &str) -> Option<KeyText> {
+        if text.len() > KeyText::CAPACITY {
+            return None;
+        }
+        let mut bytes = [0; KeyText::CAPACITY];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        Some(KeyText {
+            bytes,
+            len: text.len() as u8,
+        })
+    }
+
+    /// Returns the text as a string slice.
+    pub fn as_str(&self) -> &str {
+        // The bytes are copied from a `str` in `KeyText::new`.
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl std::ops::Deref for KeyText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for KeyText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for KeyText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<KeyText> for String {
+    fn from(text: KeyText) -> String {
+        text.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for KeyText {
+    type Error = &'static str;
+
+    fn try_from(text: String) -> Result<KeyText, Self::Error> {
+        KeyText::new(&text).ok_or("the key text is too long")
+    }
+}
+
+/// The keys a terminal reported as alternatives for a key event.
+///
+/// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/#key-codes> for more information.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct AlternateKeys {
+    /// The key without the Shift modifier applied, in the current keyboard layout.
+    pub unshifted: KeyCode,
+    /// The key with the Shift modifier applied, if Shift was pressed.
+    ///
+    /// If set, this is the [`KeyEvent::code`] and [`KeyModifiers::SHIFT`] is removed from the
+    /// event's modifiers.
+    pub shifted: Option<KeyCode>,
+    /// The key at the same physical position in the standard PC-101 (US) keyboard layout.
+    pub base_layout: Option<KeyCode>,
 }
 
 impl KeyEvent {
@@ -1016,6 +2067,8 @@ impl KeyEvent {
             modifiers,
             kind: KeyEventKind::Press,
             state: KeyEventState::empty(),
+            text: None,
+            alternate_keys: None,
         }
     }
 
@@ -1029,6 +2082,8 @@ impl KeyEvent {
             modifiers,
             kind,
             state: KeyEventState::empty(),
+            text: None,
+            alternate_keys: None,
         }
     }
 
@@ -1043,6 +2098,8 @@ impl KeyEvent {
             modifiers,
             kind,
             state,
+            text: None,
+            alternate_keys: None,
         }
     }
 
@@ -1050,17 +2107,84 @@ impl KeyEvent {
     // so that KeyModifiers::SHIFT is present iff
     // an uppercase char is present.
     fn normalize_case(mut self) -> KeyEvent {
-        let c = match self.code {
-            KeyCode::Char(c) => c,
-            _ => return self,
-        };
+        (self.code, self.modifiers) = self.normalized_code_and_modifiers();
+        self
+    }
+
+    // returns the code and modifiers as `normalize_case` would set them.
+    fn normalized_code_and_modifiers(&self) -> (KeyCode, KeyModifiers) {
+        let (mut code, mut modifiers) = (self.code, self.modifiers);
+        if let KeyCode::Char(c) = code {
+            if c.is_ascii_uppercase() {
+                modifiers.insert(KeyModifiers::SHIFT);
+            } else if modifiers.contains(KeyModifiers::SHIFT) {
+                code = KeyCode::Char(c.to_ascii_uppercase())
+            }
+        }
+        (code, modifiers)
+    }
 
-        if c.is_ascii_uppercase() {
-            self.modifiers.insert(KeyModifiers::SHIFT);
-        } else if self.modifiers.contains(KeyModifiers::SHIFT) {
-            self.code = KeyCode::Char(c.to_ascii_uppercase())
+    /// Returns the key event with the key the terminal reported before applying the Shift
+    /// modifier, and with [`KeyModifiers::SHIFT`] restored if it was applied.
+    ///
+    /// This is the key event itself if no alternate keys were reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::event::{AlternateKeys, KeyCode, KeyEvent, KeyModifiers};
+    ///
+    /// let mut event = KeyEvent::new(KeyCode::Char('('), KeyModifiers::NONE);
+    /// event.alternate_keys = Some(AlternateKeys {
+    ///     unshifted: KeyCode::Char('9'),
+    ///     shifted: Some(KeyCode::Char('(')),
+    ///     base_layout: None,
+    /// });
+    /// assert_eq!(
+    ///     event.unshifted(),
+    ///     KeyEvent::new(KeyCode::Char('9'), KeyModifiers::SHIFT)
+    /// );
+    /// ```
+    pub fn unshifted(&self) -> KeyEvent {
+        let mut event = *self;
+        if let Some(alternate_keys) = self.alternate_keys {
+            event.code = alternate_keys.unshifted;
+            if alternate_keys.shifted.is_some() {
+                event.modifiers.insert(KeyModifiers::SHIFT);
+            }
         }
-        self
+        event
+    }
+
+    /// Returns the key event with the key at the same physical position in the standard PC-101
+    /// (US) layout, which keeps keybindings like `Ctrl+C` working with other layouts.
+    ///
+    /// Like [`KeyEvent::unshifted`], [`KeyModifiers::SHIFT`] is restored if it was applied. This
+    /// is the same as [`KeyEvent::unshifted`] if the terminal didn't report a base layout key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::event::{AlternateKeys, KeyCode, KeyEvent, KeyModifiers};
+    ///
+    /// // Ctrl+С on a Russian layout.
+    /// let mut event = KeyEvent::new(KeyCode::Char('с'), KeyModifiers::CONTROL);
+    /// event.alternate_keys = Some(AlternateKeys {
+    ///     unshifted: KeyCode::Char('с'),
+    ///     shifted: None,
+    ///     base_layout: Some(KeyCode::Char('c')),
+    /// });
+    /// assert_eq!(
+    ///     event.base_layout(),
+    ///     KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+    /// );
+    /// ```
+    pub fn base_layout(&self) -> KeyEvent {
+        let mut event = self.unshifted();
+        if let Some(base_layout) = self.alternate_keys.and_then(|keys| keys.base_layout) {
+            event.code = base_layout;
+        }
+        event
     }
 
     /// Returns whether the key event is a press event.
@@ -1086,45 +2210,45 @@ impl From<KeyCode> for KeyEvent {
             modifiers: KeyModifiers::empty(),
             kind: KeyEventKind::Press,
             state: KeyEventState::empty(),
+            text: None,
+            alternate_keys: None,
         }
     }
 }
 
 impl PartialEq for KeyEvent {
     fn eq(&self, other: &KeyEvent) -> bool {
-        let KeyEvent {
-            code: lhs_code,
-            modifiers: lhs_modifiers,
-            kind: lhs_kind,
-            state: lhs_state,
-        } = self.normalize_case();
-        let KeyEvent {
-            code: rhs_code,
-            modifiers: rhs_modifiers,
-            kind: rhs_kind,
-            state: rhs_state,
-        } = other.normalize_case();
+        let (lhs_code, lhs_modifiers) = self.normalized_code_and_modifiers();
+        let (rhs_code, rhs_modifiers) = other.normalized_code_and_modifiers();
         (lhs_code == rhs_code)
             && (lhs_modifiers == rhs_modifiers)
-            && (lhs_kind == rhs_kind)
-            && (lhs_state == rhs_state)
+            && (self.kind == other.kind)
+            && (self.state == other.state)
     }
 }
 
 impl Eq for KeyEvent {}
 
+impl PartialOrd for KeyEvent {
+    fn partial_cmp(&self, other: &KeyEvent) -> Option<std::cmp::Ordering> {
+        let (lhs_code, lhs_modifiers) = self.normalized_code_and_modifiers();
+        let (rhs_code, rhs_modifiers) = other.normalized_code_and_modifiers();
+        (lhs_code, lhs_modifiers, self.kind, self.state).partial_cmp(&(
+            rhs_code,
+            rhs_modifiers,
+            other.kind,
+            other.state,
+        ))
+    }
+}
+
 impl Hash for KeyEvent {
     fn hash<H: Hasher>(&self, hash_state: &mut H) {
-        let KeyEvent {
-            code,
-            modifiers,
-            kind,
-            state,
-        } = self.normalize_case();
+        let (code, modifiers) = self.normalized_code_and_modifiers();
         code.hash(hash_state);
         modifiers.hash(hash_state);
-        kind.hash(hash_state);
-        state.hash(hash_state);
+        self.kind.hash(hash_state);
+        self.state.hash(hash_state);
     }
 }
 
@@ -1544,6 +2668,24 @@ pub(crate) enum InternalEvent {
     /// Attributes and architectural class of the terminal.
     #[cfg(unix)]
     PrimaryDeviceAttributes,
+    /// The type and version of the terminal.
+    #[cfg(unix)]
+    SecondaryDeviceAttributes(SecondaryDeviceAttributes),
+    /// A control sequence which couldn't be parsed, read while a query is in flight.
+    #[cfg(unix)]
+    ControlSequence(CsiSequence),
+    /// A pending query received its last response.
+    #[cfg(unix)]
+    QueryCompleted,
+    /// The data of an OSC (`ESC ]`) string, without the introducer and terminator.
+    #[cfg(unix)]
+    OperatingSystemCommand(Vec<u8>),
+    /// The data of a DCS (`ESC P`) string, without the introducer and terminator.
+    #[cfg(unix)]
+    DeviceControlString(Vec<u8>),
+    /// The data of an APC (`ESC _`) string, without the introducer and terminator.
+    #[cfg(unix)]
+    ApplicationProgramCommand(Vec<u8>),
 }
 
 /// The selected color scheme of the terminal.
@@ -1559,6 +2701,156 @@ pub enum ThemeMode {
     Dark,
 }
 
+/// The secondary device attributes (DA2) of the terminal, its response to `CSI > c`.
+///
+/// Terminal emulators report the terminal they emulate as the type and usually their own version
+/// as the firmware version, for example xterm 390 reports `41` and `390`.
+///
+/// See <https://vt100.net/docs/vt510-rm/DA2.html>
+#[derive(Debug, Default, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SecondaryDeviceAttributes {
+    /// The terminal type.
+    pub terminal_type: u32,
+    /// The firmware version.
+    pub firmware_version: u32,
+    /// The ROM cartridge registration number, usually `0`.
+    pub rom_cartridge: u32,
+}
+
+/// Identifies the terminal.
+///
+/// This can be queried with
+/// [`query_terminal_identity`](crate::terminal::query_terminal_identity) and is reported in
+/// [`TerminalFeatures`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerminalIdentity {
+    /// The name the terminal reports to XTVERSION (`CSI > q`), such as `kitty`, `WezTerm`, `foot`
+    /// or `XTerm`, or the name of a terminal known by its secondary device attributes: `xterm`,
+    /// `VTE`, `screen` or `tmux`.
+    pub name: Option<IdentityText>,
+    /// The version the terminal reports to XTVERSION, such as `0.35.2`, or the version of a
+    /// terminal known by its secondary device attributes, if it reports one.
+    pub version: Option<IdentityText>,
+    /// The secondary device attributes, which also identify terminals that don't support
+    /// XTVERSION.
+    pub secondary_device_attributes: Option<SecondaryDeviceAttributes>,
+}
+
+impl From<SecondaryDeviceAttributes> for TerminalIdentity {
+    /// Identifies the terminals which are known by their secondary device attributes, for the
+    /// terminals which don't support XTVERSION.
+    fn from(attributes: SecondaryDeviceAttributes) -> Self {
+        let version = attributes.firmware_version;
+        let (name, version) = match attributes.terminal_type {
+            41 => ("xterm", Some(version.to_string())),
+            // VTE reports its version 0.MINOR.MICRO as MINOR * 100 + MICRO. Other terminals, such
+            // as foot, report type 1 like older versions of VTE, so those can't be told apart.
+            65 => (
+                "VTE",
+                Some(format!("0.{}.{}", version / 100, version % 100)),
+            ),
+            // screen reports its version MAJOR.MINOR.PATCH as MAJOR * 10000 + MINOR * 100 + PATCH.
+            83 => (
+                "screen",
+                Some(format!(
+                    "{}.{}.{}",
+                    version / 10000,
+                    version / 100 % 100,
+                    version % 100
+                )),
+            ),
+            // tmux doesn't report its version.
+            84 => ("tmux", None),
+            _ => {
+                return TerminalIdentity {
+                    secondary_device_attributes: Some(attributes),
+                    ..TerminalIdentity::default()
+                }
+            }
+        };
+        TerminalIdentity {
+            name: Some(IdentityText::new(name)),
+            version: version.as_deref().map(IdentityText::new),
+            secondary_device_attributes: Some(attributes),
+        }
+    }
+}
+
+/// The name or version of a terminal in a [`TerminalIdentity`].
+///
+/// The text is stored inline, which keeps [`TerminalIdentity`] and [`TerminalFeatures`] `Copy`.
+/// Text longer than [`IdentityText::CAPACITY`] bytes is truncated. It dereferences to a `str`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", from = "String")
+)]
+#[derive(PartialOrd, PartialEq, Eq, Ord, Clone, Copy, Hash)]
+pub struct IdentityText {
+    // UTF-8, up to `len`.
+    bytes: [u8; IdentityText::CAPACITY],
+    len: u8,
+}
+
+impl IdentityText {
+    /// The maximum length of the text in bytes.
+    pub const CAPACITY: usize = 64;
+
+    /// Creates the text, truncated to [`IdentityText::CAPACITY`] bytes.
+    pub fn new(text: &str) -> IdentityText {
+        let mut len = text.len().min(IdentityText::CAPACITY);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut bytes = [0; IdentityText::CAPACITY];
+        bytes[..len].copy_from_slice(&text.as_bytes()[..len]);
+        IdentityText {
+            bytes,
+            len: len as u8,
+        }
+    }
+
+    /// Returns the text.
+    pub fn as_str(&self) -> &str {
+        // The bytes are copied up to a char boundary in `IdentityText::new`.
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl std::ops::Deref for IdentityText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for IdentityText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for IdentityText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<IdentityText> for String {
+    fn from(text: IdentityText) -> String {
+        text.as_str().to_string()
+    }
+}
+
+impl From<String> for IdentityText {
+    fn from(text: String) -> IdentityText {
+        IdentityText::new(&text)
+    }
+}
+
 /// The current state of features supported by the terminal.
 ///
 /// This can be queried with [terminal::terminal_features].
@@ -1568,6 +2860,18 @@ pub struct TerminalFeatures {
     pub keyboard_enhancement_flags: Option<KeyboardEnhancementFlags>,
     pub synchronized_output_mode: SynchronizedOutputMode,
     pub theme_mode: Option<ThemeMode>,
+    /// The default foreground color, see
+    /// [`query_foreground_color`](crate::terminal::query_foreground_color).
+    pub foreground_color: Option<Color>,
+    /// The default background color, see
+    /// [`query_background_color`](crate::terminal::query_background_color).
+    pub background_color: Option<Color>,
+    /// The color of the cursor, see
+    /// [`query_cursor_color`](crate::terminal::query_cursor_color).
+    pub cursor_color: Option<Color>,
+    /// The identity of the terminal, see
+    /// [`query_terminal_identity`](crate::terminal::query_terminal_identity).
+    pub identity: TerminalIdentity,
 }
 
 #[cfg(test)]
@@ -1580,6 +2884,57 @@ mod tests {
     use MediaKeyCode::*;
     use ModifierKeyCode::*;
 
+    #[test]
+    fn test_settings_dont_wait_for_the_reader() {
+        // Another thread blocked in `read` holds the reader.
+        let _reader = lock_internal_event_reader();
+
+        set_escape_timeout(Some(Duration::from_millis(50)));
+        #[cfg(unix)]
+        assert_eq!(escape_timeout(), Some(Duration::from_millis(50)));
+        set_escape_timeout(None);
+        set_resize_coalescing(None);
+        #[cfg(feature = "bracketed-paste")]
+        set_paste_options(PasteOptions::default());
+    }
+
+    #[test]
+    fn test_terminal_identity_from_secondary_device_attributes() {
+        let identity = |terminal_type, firmware_version| {
+            let identity = TerminalIdentity::from(SecondaryDeviceAttributes {
+                terminal_type,
+                firmware_version,
+                rom_cartridge: 0,
+            });
+            (
+                identity.name.map(String::from),
+                identity.version.map(String::from),
+            )
+        };
+        let known =
+            |name: &str, version: Option<&str>| (Some(name.to_string()), version.map(String::from));
+        assert_eq!(identity(41, 390), known("xterm", Some("390")));
+        assert_eq!(identity(65, 6800), known("VTE", Some("0.68.0")));
+        assert_eq!(identity(83, 40900), known("screen", Some("4.9.0")));
+        assert_eq!(identity(84, 0), known("tmux", None));
+        // Older versions of VTE, foot and Konsole all report type 1.
+        assert_eq!(identity(1, 6201), (None, None));
+        assert_eq!(identity(1, 11602), (None, None));
+        assert_eq!(identity(1, 115), (None, None));
+    }
+
+    #[test]
+    fn test_identity_text() {
+        assert_eq!(IdentityText::new("kitty").as_str(), "kitty");
+        let long = "é".repeat(IdentityText::CAPACITY);
+        let text = IdentityText::new(&long);
+        assert_eq!(text.len(), IdentityText::CAPACITY);
+        assert!(long.starts_with(text.as_str()));
+        // A character isn't split.
+        let long = format!("x{}", long);
+        assert_eq!(IdentityText::new(&long).len(), IdentityText::CAPACITY - 1);
+    }
+
     #[test]
     fn test_equality() {
         let lowercase_d_with_shift = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::SHIFT);
@@ -1610,6 +2965,34 @@ mod tests {
         assert_eq!(uppercase_d_hash, uppercase_d_with_shift_hash);
     }
 
+    #[test]
+    fn test_ordering() {
+        use std::cmp::Ordering;
+
+        let lowercase_d_with_shift = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::SHIFT);
+        let uppercase_d = KeyEvent::new(KeyCode::Char('D'), KeyModifiers::NONE);
+        assert_eq!(
+            lowercase_d_with_shift.partial_cmp(&uppercase_d),
+            Some(Ordering::Equal)
+        );
+
+        // The text and alternate keys are ignored, like by `PartialEq`.
+        let mut with_text_and_alternate_keys = uppercase_d;
+        with_text_and_alternate_keys.text = KeyText::new("D");
+        with_text_and_alternate_keys.alternate_keys = Some(AlternateKeys {
+            unshifted: KeyCode::Char('d'),
+            shifted: Some(KeyCode::Char('D')),
+            base_layout: None,
+        });
+        assert_eq!(
+            with_text_and_alternate_keys.partial_cmp(&uppercase_d),
+            Some(Ordering::Equal)
+        );
+
+        let e = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE);
+        assert!(uppercase_d < e);
+    }
+
     #[test]
     fn keycode_display() {
         #[cfg(target_os = "macos")]
@@ -1735,8 +3118,75 @@ mod tests {
         column: 1,
         row: 1,
         modifiers: KeyModifiers::empty(),
+        position_unit: MousePositionUnit::Cell,
     };
 
+    #[test]
+    fn test_mouse_pixel_positions() {
+        let mut enable = String::new();
+        EnableMousePixelPositions.write_ansi(&mut enable).unwrap();
+        assert_eq!(enable, "\x1B[?1016h");
+
+        // Disabling pixel positions after mouse capture doesn't enable SGR mode again.
+        let mut disable = String::new();
+        DisableMousePixelPositions.write_ansi(&mut disable).unwrap();
+        assert_eq!(disable, "\x1B[?1016l");
+    }
+
+    #[test]
+    fn test_mouse_capture_mode() {
+        let mode = MouseCaptureMode {
+            tracking: MouseTracking::ButtonDrag,
+            encoding: MouseEncoding::SgrPixels,
+        };
+        let mut enable = String::new();
+        EnableMouseCaptureMode(mode)
+            .write_ansi(&mut enable)
+            .unwrap();
+        assert_eq!(enable, "\x1B[?1000h\x1B[?1002h\x1B[?1016h");
+        let mut disable = String::new();
+        DisableMouseCaptureMode(mode)
+            .write_ansi(&mut disable)
+            .unwrap();
+        assert_eq!(disable, "\x1B[?1016l\x1B[?1002l\x1B[?1000l");
+
+        let mut enable = String::new();
+        EnableMouseCaptureMode(MouseCaptureMode {
+            tracking: MouseTracking::Click,
+            encoding: MouseEncoding::X10,
+        })
+        .write_ansi(&mut enable)
+        .unwrap();
+        assert_eq!(enable, "\x1B[?1000h");
+    }
+
+    #[test]
+    fn test_mouse_cell_position() {
+        let window_size = WindowSize {
+            rows: 10,
+            columns: 20,
+            width: 200,
+            height: 150,
+        };
+        assert_eq!(MOUSE_CLICK.cell_position(&window_size), Some((1, 1)));
+
+        let pixel_click = MouseEvent {
+            column: 35,
+            row: 44,
+            position_unit: MousePositionUnit::Pixel,
+            ..MOUSE_CLICK
+        };
+        assert_eq!(pixel_click.cell_position(&window_size), Some((3, 2)));
+        assert_eq!(
+            pixel_click.cell_position(&WindowSize {
+                width: 0,
+                height: 0,
+                ..window_size
+            }),
+            None
+        );
+    }
+
     #[cfg(feature = "derive-more")]
     #[test]
     fn event_is() {
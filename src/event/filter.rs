@@ -6,6 +6,14 @@ pub(crate) trait Filter: Send + Sync + 'static {
     fn eval(&self, event: &InternalEvent) -> bool;
 }
 
+/// Returns whether the event is a response to a query, or the response to the
+/// `PrimaryDeviceAttributes` query sent after it. Every terminal answers the latter, so if it
+/// arrives without the response to the query, the terminal doesn't support the query.
+#[cfg(unix)]
+fn matches_or_da1(event: &InternalEvent, matches: impl Fn(&InternalEvent) -> bool) -> bool {
+    matches(event) || matches!(*event, InternalEvent::PrimaryDeviceAttributes)
+}
+
 #[cfg(unix)]
 #[derive(Debug, Clone)]
 pub(crate) struct CursorPositionFilter;
@@ -24,82 +32,117 @@ pub(crate) struct KeyboardEnhancementFlagsFilter;
 #[cfg(unix)]
 impl Filter for KeyboardEnhancementFlagsFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
-        // This filter checks for either a KeyboardEnhancementFlags response or
-        // a PrimaryDeviceAttributes response. If we receive the PrimaryDeviceAttributes
-        // response but not KeyboardEnhancementFlags, the terminal does not support
-        // progressive keyboard enhancement.
-        matches!(
-            *event,
-            InternalEvent::KeyboardEnhancementFlags(_) | InternalEvent::PrimaryDeviceAttributes
-        )
+        matches_or_da1(event, |event| {
+            matches!(*event, InternalEvent::KeyboardEnhancementFlags(_))
+        })
     }
 }
 
 #[cfg(unix)]
 #[derive(Debug, Clone)]
-pub(crate) struct PrimaryDeviceAttributesFilter;
+pub(crate) struct ThemeModeFilter;
 
 #[cfg(unix)]
-impl Filter for PrimaryDeviceAttributesFilter {
+impl Filter for ThemeModeFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
-        matches!(*event, InternalEvent::PrimaryDeviceAttributes)
+        use crate::event::Event;
+        matches_or_da1(event, |event| {
+            matches!(*event, InternalEvent::Event(Event::ThemeModeChanged(_)))
+        })
     }
 }
 
 #[cfg(unix)]
 #[derive(Debug, Clone)]
-pub(crate) struct ThemeModeFilter;
+pub(crate) struct SynchronizedOutputModeFilter;
 
 #[cfg(unix)]
-impl Filter for ThemeModeFilter {
+impl Filter for SynchronizedOutputModeFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches_or_da1(event, |event| {
+            matches!(*event, InternalEvent::SynchronizedOutputMode(_))
+        })
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct TerminalFeaturesFilter;
+
+#[cfg(unix)]
+impl Filter for TerminalFeaturesFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
         use crate::event::Event;
-        // See `KeyboardEnhancementFlagsFilter` above: `PrimaryDeviceAttributes` is
-        // used to elicit a response from the terminal even if it doesn't support the
-        // theme mode query.
-        matches!(
-            *event,
-            InternalEvent::Event(Event::ThemeModeChanged(_))
-                | InternalEvent::PrimaryDeviceAttributes
-        )
+        matches_or_da1(event, |event| {
+            matches!(
+                *event,
+                InternalEvent::KeyboardEnhancementFlags(_)
+                    | InternalEvent::Event(Event::ThemeModeChanged(_))
+                    | InternalEvent::SynchronizedOutputMode(_)
+                    | InternalEvent::OperatingSystemCommand(_)
+                    | InternalEvent::DeviceControlString(_)
+                    | InternalEvent::SecondaryDeviceAttributes(_)
+            )
+        })
     }
 }
 
 #[cfg(unix)]
 #[derive(Debug, Clone)]
-pub(crate) struct SynchronizedOutputModeFilter;
+pub(crate) struct OperatingSystemCommandFilter;
 
 #[cfg(unix)]
-impl Filter for SynchronizedOutputModeFilter {
+impl Filter for OperatingSystemCommandFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
-        // See `KeyboardEnhancementFlagsFilter` above: `PrimaryDeviceAttributes` is
-        // used to elicit a response from the terminal even if it doesn't support the
-        // synchronized output mode query.
-        matches!(
-            *event,
-            InternalEvent::SynchronizedOutputMode(_) | InternalEvent::PrimaryDeviceAttributes
-        )
+        matches_or_da1(event, |event| {
+            matches!(*event, InternalEvent::OperatingSystemCommand(_))
+        })
     }
 }
 
 #[cfg(unix)]
 #[derive(Debug, Clone)]
-pub(crate) struct TerminalFeaturesFilter;
+pub(crate) struct TerminalIdentityFilter;
 
 #[cfg(unix)]
-impl Filter for TerminalFeaturesFilter {
+impl Filter for TerminalIdentityFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
-        use crate::event::Event;
-        // See `KeyboardEnhancementFlagsFilter` above: `PrimaryDeviceAttributes` is
-        // used to elicit a response from the terminal even if it doesn't support the
-        // synchronized output mode query.
-        matches!(
-            *event,
-            InternalEvent::KeyboardEnhancementFlags(_)
-                | InternalEvent::Event(Event::ThemeModeChanged(_))
-                | InternalEvent::SynchronizedOutputMode(_)
-                | InternalEvent::PrimaryDeviceAttributes
-        )
+        matches_or_da1(event, |event| {
+            matches!(
+                *event,
+                InternalEvent::DeviceControlString(_) | InternalEvent::SecondaryDeviceAttributes(_)
+            )
+        })
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct QueryResponseFilter;
+
+#[cfg(unix)]
+impl Filter for QueryResponseFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches_or_da1(event, |event| {
+            matches!(
+                *event,
+                InternalEvent::ControlSequence(_)
+                    | InternalEvent::OperatingSystemCommand(_)
+                    | InternalEvent::DeviceControlString(_)
+                    | InternalEvent::ApplicationProgramCommand(_)
+            )
+        })
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct QueryCompletedFilter;
+
+#[cfg(unix)]
+impl Filter for QueryCompletedFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches!(*event, InternalEvent::QueryCompleted)
     }
 }
 
@@ -123,7 +166,7 @@ impl Filter for EventFilter {
 mod tests {
     use super::{
         super::Event, CursorPositionFilter, EventFilter, Filter, InternalEvent,
-        KeyboardEnhancementFlagsFilter, PrimaryDeviceAttributesFilter,
+        KeyboardEnhancementFlagsFilter, QueryResponseFilter,
     };
 
     #[derive(Debug, Clone)]
@@ -153,9 +196,11 @@ mod tests {
     }
 
     #[test]
-    fn test_primary_device_attributes_filter_filters_primary_device_attributes() {
-        assert!(!PrimaryDeviceAttributesFilter.eval(&InternalEvent::Event(Event::Resize(10, 10))));
-        assert!(PrimaryDeviceAttributesFilter.eval(&InternalEvent::PrimaryDeviceAttributes));
+    fn test_query_response_filter_filters_query_responses() {
+        assert!(!QueryResponseFilter.eval(&InternalEvent::Event(Event::Resize(10, 10))));
+        assert!(!QueryResponseFilter.eval(&InternalEvent::CursorPosition(0, 0)));
+        assert!(QueryResponseFilter.eval(&InternalEvent::OperatingSystemCommand(b"11;".to_vec())));
+        assert!(QueryResponseFilter.eval(&InternalEvent::PrimaryDeviceAttributes));
     }
 
     #[test]
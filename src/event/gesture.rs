@@ -0,0 +1,285 @@
+use std::time::{Duration, Instant};
+
+use crate::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+/// A higher level mouse gesture, recognized by [`MouseGestureRecognizer`].
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MouseGesture {
+    /// A mouse button was pressed.
+    Click {
+        /// The pressed button.
+        button: MouseButton,
+        /// The number of consecutive clicks, 1 for a single click, 2 for a double click, etc.
+        count: u32,
+        /// The column of the click.
+        column: u16,
+        /// The row of the click.
+        row: u16,
+        /// The key modifiers active during the click.
+        modifiers: KeyModifiers,
+    },
+    /// The mouse started moving while a button was pressed.
+    DragStart {
+        /// The pressed button.
+        button: MouseButton,
+        /// The column where the button was pressed.
+        column: u16,
+        /// The row where the button was pressed.
+        row: u16,
+        /// The key modifiers active when the drag started.
+        modifiers: KeyModifiers,
+    },
+    /// The button of a drag was released.
+    DragEnd {
+        /// The pressed button.
+        button: MouseButton,
+        /// The column where the drag started.
+        origin_column: u16,
+        /// The row where the drag started.
+        origin_row: u16,
+        /// The column where the button was released.
+        column: u16,
+        /// The row where the button was released.
+        row: u16,
+        /// The key modifiers active when the drag ended.
+        modifiers: KeyModifiers,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastClick {
+    button: MouseButton,
+    column: u16,
+    row: u16,
+    time: Instant,
+    count: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    button: MouseButton,
+    column: u16,
+    row: u16,
+    dragging: bool,
+}
+
+/// Recognizes clicks, multi-clicks and drags in a stream of [`MouseEvent`]s.
+///
+/// Feed every mouse event to [`MouseGestureRecognizer::process`], which returns the gesture the
+/// event completes, if any. The mouse events themselves are still of interest, for example to
+/// follow a drag.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{read, Event, MouseGesture, MouseGestureRecognizer};
+///
+/// let mut recognizer = MouseGestureRecognizer::default();
+/// loop {
+///     if let Event::Mouse(mouse_event) = read()? {
+///         match recognizer.process(&mouse_event) {
+///             Some(MouseGesture::Click { count: 2, .. }) => println!("Double click"),
+///             Some(MouseGesture::DragEnd { .. }) => println!("Dropped"),
+///             _ => {}
+///         }
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MouseGestureRecognizer {
+    click_interval: Duration,
+    click_distance: u16,
+    last_click: Option<LastClick>,
+    press: Option<Press>,
+}
+
+impl Default for MouseGestureRecognizer {
+    fn default() -> Self {
+        MouseGestureRecognizer::new(Duration::from_millis(500), 1)
+    }
+}
+
+impl MouseGestureRecognizer {
+    /// Creates a recognizer counting clicks as consecutive if they are at most `click_interval`
+    /// apart and at most `click_distance` columns and rows away from the previous click.
+    ///
+    /// The distance is in pixels for events in [`MousePositionUnit::Pixel`](super::MousePositionUnit::Pixel).
+    pub fn new(click_interval: Duration, click_distance: u16) -> Self {
+        MouseGestureRecognizer {
+            click_interval,
+            click_distance,
+            last_click: None,
+            press: None,
+        }
+    }
+
+    /// Processes a mouse event which happened now.
+    pub fn process(&mut self, event: &MouseEvent) -> Option<MouseGesture> {
+        self.process_at(event, Instant::now())
+    }
+
+    /// Processes a mouse event which happened at the given time.
+    pub fn process_at(&mut self, event: &MouseEvent, time: Instant) -> Option<MouseGesture> {
+        match event.kind {
+            MouseEventKind::Down(button) => {
+                let count = match self.last_click {
+                    Some(last_click)
+                        if last_click.button == button
+                            && time.saturating_duration_since(last_click.time)
+                                <= self.click_interval
+                            && last_click.column.abs_diff(event.column) <= self.click_distance
+                            && last_click.row.abs_diff(event.row) <= self.click_distance =>
+                    {
+                        last_click.count.saturating_add(1)
+                    }
+                    _ => 1,
+                };
+                self.last_click = Some(LastClick {
+                    button,
+                    column: event.column,
+                    row: event.row,
+                    time,
+                    count,
+                });
+                self.press = Some(Press {
+                    button,
+                    column: event.column,
+                    row: event.row,
+                    dragging: false,
+                });
+                Some(MouseGesture::Click {
+                    button,
+                    count,
+                    column: event.column,
+                    row: event.row,
+                    modifiers: event.modifiers,
+                })
+            }
+            MouseEventKind::Drag(button) => {
+                // Some terminals don't report the press before the drag.
+                let press = self.press.get_or_insert(Press {
+                    button,
+                    column: event.column,
+                    row: event.row,
+                    dragging: false,
+                });
+                if press.dragging {
+                    return None;
+                }
+                press.dragging = true;
+                // A drag breaks a series of clicks.
+                self.last_click = None;
+                Some(MouseGesture::DragStart {
+                    button: press.button,
+                    column: press.column,
+                    row: press.row,
+                    modifiers: event.modifiers,
+                })
+            }
+            // Some terminals don't report which button was released, so any release ends the
+            // press.
+            MouseEventKind::Up(_) => match self.press.take() {
+                Some(press) if press.dragging => Some(MouseGesture::DragEnd {
+                    button: press.button,
+                    origin_column: press.column,
+                    origin_row: press.row,
+                    column: event.column,
+                    row: event.row,
+                    modifiers: event.modifiers,
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::MousePositionUnit;
+
+    fn mouse_event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+            position_unit: MousePositionUnit::Cell,
+        }
+    }
+
+    fn click_count(gesture: Option<MouseGesture>) -> u32 {
+        match gesture {
+            Some(MouseGesture::Click { count, .. }) => count,
+            gesture => panic!("unexpected gesture {:?}", gesture),
+        }
+    }
+
+    #[test]
+    fn test_click_counting() {
+        let mut recognizer = MouseGestureRecognizer::new(Duration::from_millis(300), 1);
+        let start = Instant::now();
+        let down = mouse_event(MouseEventKind::Down(MouseButton::Left), 10, 5);
+        let up = mouse_event(MouseEventKind::Up(MouseButton::Left), 10, 5);
+
+        assert_eq!(click_count(recognizer.process_at(&down, start)), 1);
+        assert_eq!(recognizer.process_at(&up, start), None);
+        let time = start + Duration::from_millis(200);
+        assert_eq!(click_count(recognizer.process_at(&down, time)), 2);
+        let time = time + Duration::from_millis(200);
+        let nearby_down = mouse_event(MouseEventKind::Down(MouseButton::Left), 11, 4);
+        assert_eq!(click_count(recognizer.process_at(&nearby_down, time)), 3);
+
+        // Too late.
+        let time = time + Duration::from_millis(400);
+        assert_eq!(click_count(recognizer.process_at(&down, time)), 1);
+        // Too far.
+        let far_down = mouse_event(MouseEventKind::Down(MouseButton::Left), 12, 5);
+        assert_eq!(click_count(recognizer.process_at(&far_down, time)), 1);
+        // Another button.
+        let right_down = mouse_event(MouseEventKind::Down(MouseButton::Right), 12, 5);
+        assert_eq!(click_count(recognizer.process_at(&right_down, time)), 1);
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut recognizer = MouseGestureRecognizer::default();
+        let time = Instant::now();
+        let drag = |column| mouse_event(MouseEventKind::Drag(MouseButton::Left), column, 5);
+
+        recognizer.process_at(
+            &mouse_event(MouseEventKind::Down(MouseButton::Left), 10, 5),
+            time,
+        );
+        assert_eq!(
+            recognizer.process_at(&drag(11), time),
+            Some(MouseGesture::DragStart {
+                button: MouseButton::Left,
+                column: 10,
+                row: 5,
+                modifiers: KeyModifiers::NONE,
+            })
+        );
+        assert_eq!(recognizer.process_at(&drag(12), time), None);
+        assert_eq!(
+            recognizer.process_at(
+                &mouse_event(MouseEventKind::Up(MouseButton::Left), 13, 6),
+                time
+            ),
+            Some(MouseGesture::DragEnd {
+                button: MouseButton::Left,
+                origin_column: 10,
+                origin_row: 5,
+                column: 13,
+                row: 6,
+                modifiers: KeyModifiers::NONE,
+            })
+        );
+
+        // A drag breaks a series of clicks.
+        let down = mouse_event(MouseEventKind::Down(MouseButton::Left), 13, 6);
+        assert_eq!(click_count(recognizer.process_at(&down, time)), 1);
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use crate::event::{KeyEvent, KeyEventKind, KeyEventState, ParseKeyError};
+
+/// The result of feeding a key to a [`KeyMatcher`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum KeyMatch<A> {
+    /// The keys so far are the prefix of a key sequence, more keys are needed.
+    Pending,
+    /// A key sequence was completed.
+    Matched(A),
+    /// The keys don't start any key sequence and should be handled (replayed) as ordinary input,
+    /// in order.
+    NoMatch(Vec<KeyEvent>),
+}
+
+#[derive(Debug, Clone)]
+struct Node<A> {
+    action: Option<A>,
+    children: HashMap<KeyEvent, Node<A>>,
+}
+
+impl<A> Default for Node<A> {
+    fn default() -> Self {
+        Node {
+            action: None,
+            children: HashMap::new(),
+        }
+    }
+}
+
+/// Matches key events against key sequences, such as `g g` or `C-x C-s`.
+///
+/// Keys are compared like [`KeyEvent`]s are compared for equality, which ignores the case of
+/// characters typed with Shift, but key repeats match like presses and the key event state is
+/// ignored.
+///
+/// A key sequence can also be the prefix of longer sequences, for example `g` and `g g`. When the
+/// keys typed so far match such an ambiguous prefix, the matcher waits for the next key. If the
+/// next key doesn't continue a longer sequence or the [timeout](KeyMatcher::set_timeout) elapses,
+/// the prefix is matched.
+///
+/// Feeding a key can complete more than one result, e.g. `g x` with `g` bound matches `g` and
+/// doesn't match `x`. [`KeyMatcher::feed`] returns the first result, call [`KeyMatcher::poll`]
+/// afterwards for the others and to check the timeout.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::{Duration, Instant};
+///
+/// use crossterm::event::{self, Event, KeyMatch, KeyMatcher};
+///
+/// let mut matcher = KeyMatcher::new();
+/// matcher.bind_notation("g g", "go to top").unwrap();
+/// matcher.bind_notation("g", "go to").unwrap();
+/// matcher.bind_notation("C-x C-s", "save").unwrap();
+/// matcher.set_timeout(Some(Duration::from_millis(500)));
+///
+/// let handle = |result| match result {
+///     KeyMatch::Matched(action) => println!("{}", action),
+///     KeyMatch::NoMatch(keys) => println!("unbound keys {:?}", keys),
+///     KeyMatch::Pending => {}
+/// };
+/// loop {
+///     let timeout = matcher
+///         .deadline()
+///         .map_or(Duration::from_secs(60), |deadline| {
+///             deadline.saturating_duration_since(Instant::now())
+///         });
+///     if event::poll(timeout)? {
+///         if let Event::Key(key) = event::read()? {
+///             handle(matcher.feed(&key));
+///         }
+///     }
+///     while let Some(result) = matcher.poll() {
+///         handle(result);
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyMatcher<A> {
+    root: Node<A>,
+    timeout: Option<Duration>,
+    pending: Vec<KeyEvent>,
+    deadline: Option<Instant>,
+    results: VecDeque<KeyMatch<A>>,
+}
+
+impl<A> Default for KeyMatcher<A> {
+    fn default() -> Self {
+        KeyMatcher::new()
+    }
+}
+
+/// Returns the key as it is stored in the trie.
+fn trie_key(key: &KeyEvent) -> KeyEvent {
+    KeyEvent::new_with_kind_and_state(
+        key.code,
+        key.modifiers,
+        KeyEventKind::Press,
+        KeyEventState::NONE,
+    )
+}
+
+impl<A> KeyMatcher<A> {
+    /// Creates a matcher without key sequences and without a timeout.
+    pub fn new() -> Self {
+        KeyMatcher {
+            root: Node::default(),
+            timeout: None,
+            pending: Vec::new(),
+            deadline: None,
+            results: VecDeque::new(),
+        }
+    }
+
+    /// Sets how long to wait for the next key of a sequence.
+    ///
+    /// When the timeout elapses, the longest bound prefix of the keys typed so far is matched and
+    /// the other keys aren't. The timeout restarts with every key. Without a timeout, which is the
+    /// default, the matcher waits until a key doesn't continue the sequence, or
+    /// [`KeyMatcher::flush`] is called.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Binds a key sequence to an action, returning the action previously bound to it.
+    ///
+    /// Empty key sequences are ignored.
+    pub fn bind(&mut self, keys: impl IntoIterator<Item = KeyEvent>, action: A) -> Option<A> {
+        let mut keys = keys.into_iter().peekable();
+        keys.peek()?;
+        let node = keys.fold(&mut self.root, |node, key| {
+            node.children.entry(trie_key(&key)).or_default()
+        });
+        node.action.replace(action)
+    }
+
+    /// Binds a key sequence written as whitespace separated keys in key notation, e.g.
+    /// `"C-x C-s"`. See [`KeyEvent::from_str`](std::str::FromStr::from_str) for the notation.
+    pub fn bind_notation(&mut self, keys: &str, action: A) -> Result<Option<A>, ParseKeyError> {
+        let keys = keys
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<KeyEvent>, _>>()?;
+        Ok(self.bind(keys, action))
+    }
+
+    /// Removes the action bound to a key sequence and returns it.
+    pub fn unbind(&mut self, keys: &[KeyEvent]) -> Option<A> {
+        fn unbind<A>(node: &mut Node<A>, keys: &[KeyEvent]) -> Option<A> {
+            let (key, rest) = match keys.split_first() {
+                Some(split) => split,
+                None => return node.action.take(),
+            };
+            let key = trie_key(key);
+            let child = node.children.get_mut(&key)?;
+            let action = unbind(child, rest);
+            if child.action.is_none() && child.children.is_empty() {
+                node.children.remove(&key);
+            }
+            action
+        }
+
+        if keys.is_empty() {
+            return None;
+        }
+        unbind(&mut self.root, keys)
+    }
+
+    /// Returns the keys of the sequence currently being typed.
+    pub fn pending(&self) -> &[KeyEvent] {
+        &self.pending
+    }
+
+    /// Returns when the sequence being typed times out, if a timeout is set.
+    ///
+    /// Call [`KeyMatcher::poll`] once it has passed.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Cancels the sequence being typed and returns its keys.
+    pub fn cancel(&mut self) -> Vec<KeyEvent> {
+        self.deadline = None;
+        std::mem::take(&mut self.pending)
+    }
+
+    fn lookup(&self, keys: &[KeyEvent]) -> Option<&Node<A>> {
+        keys.iter()
+            .try_fold(&self.root, |node, key| node.children.get(&trie_key(key)))
+    }
+}
+
+impl<A: Clone> KeyMatcher<A> {
+    /// Feeds a key event which happened now and returns the first result it completes.
+    ///
+    /// Key release events don't affect the sequence being typed and are returned as
+    /// [`KeyMatch::NoMatch`].
+    pub fn feed(&mut self, key: &KeyEvent) -> KeyMatch<A> {
+        self.feed_at(key, Instant::now())
+    }
+
+    /// Feeds a key event which happened at the given time and returns the first result it
+    /// completes.
+    pub fn feed_at(&mut self, key: &KeyEvent, time: Instant) -> KeyMatch<A> {
+        if key.kind == KeyEventKind::Release {
+            self.push_result(KeyMatch::NoMatch(vec![*key]));
+        } else {
+            self.pending.push(*key);
+            self.resolve(false, time);
+        }
+        self.results.pop_front().unwrap_or(KeyMatch::Pending)
+    }
+
+    /// Returns the next result not yet returned by [`KeyMatcher::feed`], or the result of an
+    /// ambiguous prefix whose timeout elapsed.
+    pub fn poll(&mut self) -> Option<KeyMatch<A>> {
+        self.poll_at(Instant::now())
+    }
+
+    /// Like [`KeyMatcher::poll`], checking the timeout at the given time.
+    pub fn poll_at(&mut self, time: Instant) -> Option<KeyMatch<A>> {
+        if self.results.is_empty() && self.deadline.map_or(false, |deadline| time >= deadline) {
+            self.resolve(true, time);
+        }
+        self.results.pop_front()
+    }
+
+    /// Ends the sequence being typed as if the timeout elapsed and returns the next result.
+    pub fn flush(&mut self) -> Option<KeyMatch<A>> {
+        if !self.pending.is_empty() {
+            self.resolve(true, Instant::now());
+        }
+        self.results.pop_front()
+    }
+
+    fn push_result(&mut self, result: KeyMatch<A>) {
+        match (self.results.back_mut(), result) {
+            (Some(KeyMatch::NoMatch(keys)), KeyMatch::NoMatch(more)) => keys.extend(more),
+            (_, result) => self.results.push_back(result),
+        }
+    }
+
+    /// Matches the pending keys as far as possible. With `force`, the pending keys are not
+    /// continued by further keys.
+    fn resolve(&mut self, mut force: bool, time: Instant) {
+        while !self.pending.is_empty() {
+            let result = match self.lookup(&self.pending) {
+                Some(node) if !force && !node.children.is_empty() => {
+                    self.deadline = self.timeout.map(|timeout| time + timeout);
+                    return;
+                }
+                Some(Node {
+                    action: Some(action),
+                    ..
+                }) => {
+                    let result = KeyMatch::Matched(action.clone());
+                    self.pending.clear();
+                    result
+                }
+                _ => {
+                    // Match the longest prefix bound to an action, otherwise the first key
+                    // doesn't start any sequence.
+                    let prefix = (1..self.pending.len()).rev().find_map(|len| {
+                        let action = self.lookup(&self.pending[..len])?.action.clone()?;
+                        Some((len, KeyMatch::Matched(action)))
+                    });
+                    let (len, result) =
+                        prefix.unwrap_or_else(|| (1, KeyMatch::NoMatch(vec![self.pending[0]])));
+                    self.pending.drain(..len);
+                    result
+                }
+            };
+            self.push_result(result);
+            // The remaining keys were typed after the matched ones, so they may be continued.
+            force = false;
+        }
+        self.deadline = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{KeyCode, KeyModifiers};
+
+    fn key(notation: &str) -> KeyEvent {
+        notation.parse().unwrap()
+    }
+
+    fn keys(notation: &str) -> Vec<KeyEvent> {
+        notation.split_whitespace().map(key).collect()
+    }
+
+    fn matcher() -> KeyMatcher<&'static str> {
+        let mut matcher = KeyMatcher::new();
+        matcher.bind_notation("g g", "top").unwrap();
+        matcher.bind_notation("g", "goto").unwrap();
+        matcher.bind_notation("C-x C-s", "save").unwrap();
+        matcher.bind_notation("j k", "escape").unwrap();
+        matcher
+    }
+
+    #[test]
+    fn test_match_sequences() {
+        let mut matcher = matcher();
+        let time = Instant::now();
+
+        assert_eq!(matcher.feed_at(&key("C-x"), time), KeyMatch::Pending);
+        assert_eq!(matcher.pending(), keys("C-x"));
+        assert_eq!(
+            matcher.feed_at(&key("C-s"), time),
+            KeyMatch::Matched("save")
+        );
+        assert!(matcher.pending().is_empty());
+
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Pending);
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Matched("top"));
+
+        // Shifted characters match regardless of case, repeats match like presses.
+        let shifted = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        let mut repeat = key("g");
+        repeat.kind = KeyEventKind::Repeat;
+        matcher.bind([shifted], "bottom");
+        assert_eq!(
+            matcher.feed_at(&key("S-g"), time),
+            KeyMatch::Matched("bottom")
+        );
+        assert_eq!(matcher.feed_at(&repeat, time), KeyMatch::Pending);
+        assert_eq!(matcher.feed_at(&repeat, time), KeyMatch::Matched("top"));
+        assert_eq!(matcher.poll_at(time), None);
+    }
+
+    #[test]
+    fn test_no_match() {
+        let mut matcher = matcher();
+        let time = Instant::now();
+
+        assert_eq!(
+            matcher.feed_at(&key("x"), time),
+            KeyMatch::NoMatch(keys("x"))
+        );
+        assert_eq!(matcher.feed_at(&key("j"), time), KeyMatch::Pending);
+        assert_eq!(
+            matcher.feed_at(&key("x"), time),
+            KeyMatch::NoMatch(keys("j x"))
+        );
+        assert_eq!(matcher.poll_at(time), None);
+
+        // The second `j` may start the sequence again.
+        assert_eq!(matcher.feed_at(&key("j"), time), KeyMatch::Pending);
+        assert_eq!(
+            matcher.feed_at(&key("j"), time),
+            KeyMatch::NoMatch(keys("j"))
+        );
+        assert_eq!(
+            matcher.feed_at(&key("k"), time),
+            KeyMatch::Matched("escape")
+        );
+
+        // Releases don't interrupt a sequence.
+        let mut release = key("j");
+        release.kind = KeyEventKind::Release;
+        assert_eq!(matcher.feed_at(&key("j"), time), KeyMatch::Pending);
+        assert_eq!(
+            matcher.feed_at(&release, time),
+            KeyMatch::NoMatch(vec![release])
+        );
+        assert_eq!(
+            matcher.feed_at(&key("k"), time),
+            KeyMatch::Matched("escape")
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_prefix() {
+        let mut matcher = matcher();
+        let time = Instant::now();
+
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Pending);
+        assert_eq!(matcher.deadline(), None);
+        assert_eq!(matcher.poll_at(time), None);
+        assert_eq!(matcher.feed_at(&key("x"), time), KeyMatch::Matched("goto"));
+        assert_eq!(matcher.poll_at(time), Some(KeyMatch::NoMatch(keys("x"))));
+        assert_eq!(matcher.poll_at(time), None);
+
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Pending);
+        assert_eq!(
+            matcher.feed_at(&key("C-x"), time),
+            KeyMatch::Matched("goto")
+        );
+        assert_eq!(matcher.poll_at(time), None);
+        assert_eq!(matcher.pending(), keys("C-x"));
+        assert_eq!(
+            matcher.feed_at(&key("C-s"), time),
+            KeyMatch::Matched("save")
+        );
+
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Pending);
+        assert_eq!(matcher.flush(), Some(KeyMatch::Matched("goto")));
+        assert_eq!(matcher.feed_at(&key("j"), time), KeyMatch::Pending);
+        assert_eq!(matcher.cancel(), keys("j"));
+        assert_eq!(matcher.flush(), None);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut matcher = matcher();
+        matcher.set_timeout(Some(Duration::from_millis(100)));
+        let start = Instant::now();
+
+        assert_eq!(matcher.feed_at(&key("g"), start), KeyMatch::Pending);
+        assert_eq!(matcher.deadline(), Some(start + Duration::from_millis(100)));
+        let time = start + Duration::from_millis(50);
+        assert_eq!(matcher.poll_at(time), None);
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Matched("top"));
+        assert_eq!(matcher.deadline(), None);
+
+        assert_eq!(matcher.feed_at(&key("g"), time), KeyMatch::Pending);
+        let time = time + Duration::from_millis(100);
+        assert_eq!(matcher.poll_at(time), Some(KeyMatch::Matched("goto")));
+        assert_eq!(matcher.poll_at(time), None);
+
+        // A prefix without an action is replayed.
+        assert_eq!(matcher.feed_at(&key("C-x"), time), KeyMatch::Pending);
+        let time = time + Duration::from_millis(100);
+        assert_eq!(matcher.poll_at(time), Some(KeyMatch::NoMatch(keys("C-x"))));
+    }
+
+    #[test]
+    fn test_unbind() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.unbind(&keys("g g")), Some("top"));
+        assert_eq!(matcher.unbind(&keys("g g")), None);
+        assert_eq!(matcher.bind_notation("g", "go"), Ok(Some("goto")));
+        // `g` is no longer a prefix.
+        assert_eq!(matcher.feed(&key("g")), KeyMatch::Matched("go"));
+        assert_eq!(matcher.unbind(&keys("C-x")), None);
+        assert_eq!(matcher.unbind(&keys("C-x C-s")), Some("save"));
+        assert!(matcher.lookup(&keys("C-x")).is_none());
+    }
+}
@@ -0,0 +1,550 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::event::{KeyCode, KeyEvent, KeyModifiers, MediaKeyCode, ModifierKeyCode};
+
+/// The modifiers in the order and with the names written by the [`KeyEvent`] notation.
+const MODIFIER_NAMES: [(KeyModifiers, &str); 6] = [
+    (KeyModifiers::CONTROL, "C"),
+    (KeyModifiers::SHIFT, "S"),
+    (KeyModifiers::ALT, "A"),
+    (KeyModifiers::SUPER, "Super"),
+    (KeyModifiers::HYPER, "Hyper"),
+    (KeyModifiers::META, "Meta"),
+];
+
+/// An error returned when parsing a [`KeyEvent`], [`KeyCode`] or [`KeyModifiers`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyError {
+    kind: ParseKeyErrorKind,
+    token: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseKeyErrorKind {
+    Key,
+    Modifier,
+}
+
+impl ParseKeyError {
+    fn key(token: &str) -> Self {
+        ParseKeyError {
+            kind: ParseKeyErrorKind::Key,
+            token: token.to_string(),
+        }
+    }
+
+    fn modifier(token: &str) -> Self {
+        ParseKeyError {
+            kind: ParseKeyErrorKind::Modifier,
+            token: token.to_string(),
+        }
+    }
+}
+
+impl Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, self.token.is_empty()) {
+            (ParseKeyErrorKind::Key, true) => f.write_str("missing key"),
+            (ParseKeyErrorKind::Key, false) => write!(f, "unknown key `{}`", self.token),
+            (ParseKeyErrorKind::Modifier, true) => f.write_str("missing modifier"),
+            (ParseKeyErrorKind::Modifier, false) => {
+                write!(f, "unknown modifier `{}`", self.token)
+            }
+        }
+    }
+}
+
+impl Error for ParseKeyError {}
+
+/// Lowercases a name and strips its whitespace, so `"Page Up"` matches `"pageup"`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Parses a normalized long modifier name, as written by the [`KeyModifiers`] `Display`.
+fn parse_modifier_name(name: &str) -> Option<KeyModifiers> {
+    match name {
+        "shift" => Some(KeyModifiers::SHIFT),
+        "control" | "ctrl" => Some(KeyModifiers::CONTROL),
+        "alt" | "option" => Some(KeyModifiers::ALT),
+        "super" | "command" | "cmd" | "windows" | "win" => Some(KeyModifiers::SUPER),
+        "hyper" => Some(KeyModifiers::HYPER),
+        "meta" => Some(KeyModifiers::META),
+        _ => None,
+    }
+}
+
+fn parse_modifier(token: &str) -> Result<KeyModifiers, ParseKeyError> {
+    let modifier = match normalize(token).as_str() {
+        "c" => Some(KeyModifiers::CONTROL),
+        "s" => Some(KeyModifiers::SHIFT),
+        "a" => Some(KeyModifiers::ALT),
+        name => parse_modifier_name(name),
+    };
+    modifier.ok_or_else(|| ParseKeyError::modifier(token))
+}
+
+/// Parses modifiers separated by `-` or `+`.
+fn parse_modifiers(s: &str) -> Result<KeyModifiers, ParseKeyError> {
+    s.split(['-', '+'])
+        .try_fold(KeyModifiers::NONE, |modifiers, token| {
+            Ok(modifiers | parse_modifier(token)?)
+        })
+}
+
+fn parse_media_key_code(name: &str) -> Option<MediaKeyCode> {
+    match name {
+        "play" => Some(MediaKeyCode::Play),
+        "mediapause" => Some(MediaKeyCode::Pause),
+        "play/pause" | "playpause" => Some(MediaKeyCode::PlayPause),
+        "reverse" => Some(MediaKeyCode::Reverse),
+        "stop" => Some(MediaKeyCode::Stop),
+        "fastforward" => Some(MediaKeyCode::FastForward),
+        "rewind" => Some(MediaKeyCode::Rewind),
+        "nexttrack" | "tracknext" => Some(MediaKeyCode::TrackNext),
+        "previoustrack" | "trackprevious" => Some(MediaKeyCode::TrackPrevious),
+        "record" => Some(MediaKeyCode::Record),
+        "lowervolume" => Some(MediaKeyCode::LowerVolume),
+        "raisevolume" => Some(MediaKeyCode::RaiseVolume),
+        "mutevolume" => Some(MediaKeyCode::MuteVolume),
+        _ => None,
+    }
+}
+
+fn parse_modifier_key_code(name: &str) -> Option<ModifierKeyCode> {
+    match name {
+        "isolevel3shift" => return Some(ModifierKeyCode::IsoLevel3Shift),
+        "isolevel5shift" => return Some(ModifierKeyCode::IsoLevel5Shift),
+        _ => {}
+    }
+
+    let (left, name) = match name.strip_prefix("left") {
+        Some(name) => (true, name),
+        None => (false, name.strip_prefix("right")?),
+    };
+    let code = match (parse_modifier_name(name)?, left) {
+        (KeyModifiers::SHIFT, true) => ModifierKeyCode::LeftShift,
+        (KeyModifiers::CONTROL, true) => ModifierKeyCode::LeftControl,
+        (KeyModifiers::ALT, true) => ModifierKeyCode::LeftAlt,
+        (KeyModifiers::SUPER, true) => ModifierKeyCode::LeftSuper,
+        (KeyModifiers::HYPER, true) => ModifierKeyCode::LeftHyper,
+        (KeyModifiers::META, true) => ModifierKeyCode::LeftMeta,
+        (KeyModifiers::SHIFT, false) => ModifierKeyCode::RightShift,
+        (KeyModifiers::CONTROL, false) => ModifierKeyCode::RightControl,
+        (KeyModifiers::ALT, false) => ModifierKeyCode::RightAlt,
+        (KeyModifiers::SUPER, false) => ModifierKeyCode::RightSuper,
+        (KeyModifiers::HYPER, false) => ModifierKeyCode::RightHyper,
+        (KeyModifiers::META, false) => ModifierKeyCode::RightMeta,
+        _ => unreachable!(),
+    };
+    Some(code)
+}
+
+impl FromStr for KeyCode {
+    type Err = ParseKeyError;
+
+    /// Parses a key code.
+    ///
+    /// A single character is parsed as [`KeyCode::Char`]. Any other name is case insensitive and
+    /// may contain spaces, so both the [`KeyEvent`] notation (`"PageUp"`) and the `Display` of
+    /// `KeyCode` (`"Page Up"`) are accepted. See [`KeyEvent::from_str`] for the names. The
+    /// `Display` of the media Pause key is the same as the one of [`KeyCode::Pause`], so it's
+    /// only parsed from `"MediaPause"`.
+    ///
+    /// # Platform-specific Notes
+    ///
+    /// Matching the `Display` of `KeyCode`, `"Delete"` is the Backspace key on macOS and the Delete
+    /// key on other platforms. Use `"Backspace"` and `"Del"` to be portable.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(KeyCode::Char(c));
+        }
+
+        let name = normalize(s);
+        let code = match name.as_str() {
+            "backspace" => KeyCode::Backspace,
+            #[cfg(target_os = "macos")]
+            "delete" => KeyCode::Backspace,
+            #[cfg(not(target_os = "macos"))]
+            "delete" => KeyCode::Delete,
+            "del" | "fwddel" => KeyCode::Delete,
+            "enter" | "return" => KeyCode::Enter,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "insert" => KeyCode::Insert,
+            "space" => KeyCode::Char(' '),
+            "null" => KeyCode::Null,
+            "esc" | "escape" => KeyCode::Esc,
+            "capslock" => KeyCode::CapsLock,
+            "scrolllock" => KeyCode::ScrollLock,
+            "numlock" => KeyCode::NumLock,
+            "printscreen" => KeyCode::PrintScreen,
+            "pause" => KeyCode::Pause,
+            "menu" => KeyCode::Menu,
+            "begin" | "keypadbegin" => KeyCode::KeypadBegin,
+            name => match name.strip_prefix('f') {
+                Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                    KeyCode::F(n.parse().map_err(|_| ParseKeyError::key(s))?)
+                }
+                _ => parse_media_key_code(name)
+                    .map(KeyCode::Media)
+                    .or_else(|| parse_modifier_key_code(name).map(KeyCode::Modifier))
+                    .ok_or_else(|| ParseKeyError::key(s))?,
+            },
+        };
+        Ok(code)
+    }
+}
+
+impl FromStr for KeyModifiers {
+    type Err = ParseKeyError;
+
+    /// Parses key modifiers separated by `-` or `+`, such as `"C-S"` or the `Display` of
+    /// `KeyModifiers` (`"Shift+Control"`). An empty string is [`KeyModifiers::NONE`]. See
+    /// [`KeyEvent::from_str`] for the names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(KeyModifiers::NONE);
+        }
+        parse_modifiers(s)
+    }
+}
+
+impl FromStr for KeyEvent {
+    type Err = ParseKeyError;
+
+    /// Parses a key press written in key notation, such as `"C-S-a"` or `"<A-Enter>"`.
+    ///
+    /// # Grammar
+    ///
+    /// ```text
+    /// notation  = "<" chord ">" | chord
+    /// chord     = *(modifier separator) key
+    /// separator = "-" | "+"
+    /// ```
+    ///
+    /// Modifier names are case insensitive:
+    ///
+    /// | Modifier | Names                                             |
+    /// |----------|---------------------------------------------------|
+    /// | Control  | `C`, `Ctrl`, `Control`                            |
+    /// | Shift    | `S`, `Shift`                                      |
+    /// | Alt      | `A`, `Alt`, `Option`                              |
+    /// | Super    | `Super`, `Cmd`, `Command`, `Win`, `Windows`       |
+    /// | Hyper    | `Hyper`                                           |
+    /// | Meta     | `Meta`                                            |
+    ///
+    /// A key is a single character, including `-`, `+`, `<` and `>` (as in `"C--"`), or one of
+    /// the following case insensitive names, where spaces are ignored:
+    ///
+    /// - `Space`, `Backspace`, `Enter` (`Return`), `Esc` (`Escape`), `Tab`, `BackTab`, `Del`
+    ///   (`Fwd Del`), `Insert`, `Left`, `Right`, `Up`, `Down`, `Home`, `End`, `PageUp`,
+    ///   `PageDown`, `CapsLock`, `ScrollLock`, `NumLock`, `PrintScreen`, `Pause`, `Menu`,
+    ///   `KeypadBegin` (`Begin`) and `Null`;
+    /// - `F1`, `F2`, ... for the function keys;
+    /// - `Play`, `MediaPause`, `PlayPause` (`Play/Pause`), `Reverse`, `Stop`, `FastForward`,
+    ///   `Rewind`, `TrackNext` (`Next Track`), `TrackPrevious` (`Previous Track`), `Record`,
+    ///   `LowerVolume`, `RaiseVolume` and `MuteVolume` for the media keys;
+    /// - `Left` or `Right` followed by a long modifier name, such as `LeftShift` or
+    ///   `Right Ctrl`, and `IsoLevel3Shift` and `IsoLevel5Shift` for the modifier keys.
+    ///
+    /// The parsed event is a [`KeyEventKind::Press`](crate::event::KeyEventKind::Press) without
+    /// any [`KeyEventState`](crate::event::KeyEventState).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    ///
+    /// let key: KeyEvent = "C-S-a".parse().unwrap();
+    /// assert_eq!(
+    ///     key,
+    ///     KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+    /// );
+    /// assert_eq!("<A-Enter>".parse(), Ok(KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT)));
+    /// assert_eq!(key.to_string(), "C-S-a");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let notation = match s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(inner) if !inner.is_empty() => inner,
+            _ => s,
+        };
+
+        // The key follows the last separator, but may be a separator itself.
+        let last = notation.char_indices().next_back().map_or(0, |(i, _)| i);
+        let (modifiers, code) = match notation[..last].rfind(['-', '+']) {
+            Some(i) => (parse_modifiers(&notation[..i])?, &notation[i + 1..]),
+            None => (KeyModifiers::NONE, notation),
+        };
+        Ok(KeyEvent::new(code.parse()?, modifiers))
+    }
+}
+
+impl Display for KeyEvent {
+    /// Formats the key code and modifiers of the key event in the notation parsed by
+    /// [`KeyEvent::from_str`], such as `C-S-a` or `A-Enter`.
+    ///
+    /// Unlike the `Display` of [`KeyCode`] and [`KeyModifiers`], the notation doesn't depend on
+    /// the platform.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in MODIFIER_NAMES {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}-", name)?;
+            }
+        }
+
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space",
+            KeyCode::Char(c) => return write!(f, "{}", c),
+            KeyCode::F(n) => return write!(f, "F{}", n),
+            KeyCode::Backspace => "Backspace",
+            KeyCode::Enter => "Enter",
+            KeyCode::Left => "Left",
+            KeyCode::Right => "Right",
+            KeyCode::Up => "Up",
+            KeyCode::Down => "Down",
+            KeyCode::Home => "Home",
+            KeyCode::End => "End",
+            KeyCode::PageUp => "PageUp",
+            KeyCode::PageDown => "PageDown",
+            KeyCode::Tab => "Tab",
+            KeyCode::BackTab => "BackTab",
+            KeyCode::Delete => "Del",
+            KeyCode::Insert => "Insert",
+            KeyCode::Null => "Null",
+            KeyCode::Esc => "Esc",
+            KeyCode::CapsLock => "CapsLock",
+            KeyCode::ScrollLock => "ScrollLock",
+            KeyCode::NumLock => "NumLock",
+            KeyCode::PrintScreen => "PrintScreen",
+            KeyCode::Pause => "Pause",
+            KeyCode::Menu => "Menu",
+            KeyCode::KeypadBegin => "KeypadBegin",
+            KeyCode::Media(media) => match media {
+                MediaKeyCode::Play => "Play",
+                MediaKeyCode::Pause => "MediaPause",
+                MediaKeyCode::PlayPause => "PlayPause",
+                MediaKeyCode::Reverse => "Reverse",
+                MediaKeyCode::Stop => "Stop",
+                MediaKeyCode::FastForward => "FastForward",
+                MediaKeyCode::Rewind => "Rewind",
+                MediaKeyCode::TrackNext => "TrackNext",
+                MediaKeyCode::TrackPrevious => "TrackPrevious",
+                MediaKeyCode::Record => "Record",
+                MediaKeyCode::LowerVolume => "LowerVolume",
+                MediaKeyCode::RaiseVolume => "RaiseVolume",
+                MediaKeyCode::MuteVolume => "MuteVolume",
+            },
+            KeyCode::Modifier(modifier) => match modifier {
+                ModifierKeyCode::LeftShift => "LeftShift",
+                ModifierKeyCode::LeftControl => "LeftControl",
+                ModifierKeyCode::LeftAlt => "LeftAlt",
+                ModifierKeyCode::LeftSuper => "LeftSuper",
+                ModifierKeyCode::LeftHyper => "LeftHyper",
+                ModifierKeyCode::LeftMeta => "LeftMeta",
+                ModifierKeyCode::RightShift => "RightShift",
+                ModifierKeyCode::RightControl => "RightControl",
+                ModifierKeyCode::RightAlt => "RightAlt",
+                ModifierKeyCode::RightSuper => "RightSuper",
+                ModifierKeyCode::RightHyper => "RightHyper",
+                ModifierKeyCode::RightMeta => "RightMeta",
+                ModifierKeyCode::IsoLevel3Shift => "IsoLevel3Shift",
+                ModifierKeyCode::IsoLevel5Shift => "IsoLevel5Shift",
+            },
+        };
+        f.write_str(name)
+    }
+}
+
+/// (De)serializes a [`KeyEvent`] as a string in key notation, see [`KeyEvent::from_str`].
+///
+/// # Examples
+///
+/// ```
+/// use crossterm::event::KeyEvent;
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Binding {
+///     #[serde(with = "crossterm::event::key_notation")]
+///     key: KeyEvent,
+///     command: String,
+/// }
+/// ```
+#[cfg(feature = "serde")]
+pub mod key_notation {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::event::KeyEvent;
+
+    /// Serializes the key event in key notation.
+    pub fn serialize<S: Serializer>(key: &KeyEvent, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
+    /// Deserializes a key event from key notation.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyEvent, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key_event() {
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        assert_eq!("a".parse(), Ok(key(KeyCode::Char('a'), KeyModifiers::NONE)));
+        assert_eq!("C-S-a".parse(), Ok(key(KeyCode::Char('a'), ctrl_shift)));
+        assert_eq!(
+            "<A-Enter>".parse(),
+            Ok(key(KeyCode::Enter, KeyModifiers::ALT))
+        );
+        assert_eq!(
+            "Control+Shift+Page Up".parse(),
+            Ok(key(KeyCode::PageUp, ctrl_shift))
+        );
+        assert_eq!(
+            "C--".parse(),
+            Ok(key(KeyCode::Char('-'), KeyModifiers::CONTROL))
+        );
+        assert_eq!("-".parse(), Ok(key(KeyCode::Char('-'), KeyModifiers::NONE)));
+        assert_eq!(
+            "A-+".parse(),
+            Ok(key(KeyCode::Char('+'), KeyModifiers::ALT))
+        );
+        assert_eq!("<".parse(), Ok(key(KeyCode::Char('<'), KeyModifiers::NONE)));
+        assert_eq!(
+            "<C->>".parse(),
+            Ok(key(KeyCode::Char('>'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "S-F12".parse(),
+            Ok(key(KeyCode::F(12), KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            "c-space".parse(),
+            Ok(key(KeyCode::Char(' '), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "Super-Play/Pause".parse(),
+            Ok(key(
+                KeyCode::Media(MediaKeyCode::PlayPause),
+                KeyModifiers::SUPER
+            ))
+        );
+        assert_eq!(
+            "Right Ctrl".parse(),
+            Ok(key(
+                KeyCode::Modifier(ModifierKeyCode::RightControl),
+                KeyModifiers::NONE
+            ))
+        );
+
+        assert_eq!("X-a".parse::<KeyEvent>(), Err(ParseKeyError::modifier("X")));
+        assert_eq!("C-Foo".parse::<KeyEvent>(), Err(ParseKeyError::key("Foo")));
+        assert_eq!("F256".parse::<KeyEvent>(), Err(ParseKeyError::key("F256")));
+        assert_eq!("".parse::<KeyEvent>(), Err(ParseKeyError::key("")));
+    }
+
+    #[test]
+    fn test_key_event_round_trip() {
+        let codes = [
+            KeyCode::Char('a'),
+            KeyCode::Char('A'),
+            KeyCode::Char(' '),
+            KeyCode::Char('-'),
+            KeyCode::Char('+'),
+            KeyCode::Char('<'),
+            KeyCode::Char('é'),
+            KeyCode::F(1),
+            KeyCode::Backspace,
+            KeyCode::Delete,
+            KeyCode::Enter,
+            KeyCode::PageDown,
+            KeyCode::BackTab,
+            KeyCode::Pause,
+            KeyCode::KeypadBegin,
+            KeyCode::Media(MediaKeyCode::Pause),
+            KeyCode::Media(MediaKeyCode::TrackNext),
+            KeyCode::Modifier(ModifierKeyCode::LeftSuper),
+            KeyCode::Modifier(ModifierKeyCode::IsoLevel5Shift),
+        ];
+        let modifiers = [
+            KeyModifiers::NONE,
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            KeyModifiers::all(),
+        ];
+        for code in codes {
+            // The media Pause key is displayed like the Pause key.
+            if code != KeyCode::Media(MediaKeyCode::Pause) {
+                assert_eq!(code.to_string().parse(), Ok(code));
+            }
+            for modifiers in modifiers {
+                let event = key(code, modifiers);
+                assert_eq!(event.to_string().parse(), Ok(event));
+                assert_eq!(format!("<{}>", event).parse(), Ok(event));
+            }
+        }
+        for modifiers in modifiers {
+            assert_eq!(modifiers.to_string().parse(), Ok(modifiers));
+        }
+    }
+
+    #[test]
+    fn test_display_key_event() {
+        let event = key(
+            KeyCode::Char('a'),
+            KeyModifiers::SHIFT | KeyModifiers::CONTROL,
+        );
+        assert_eq!(event.to_string(), "C-S-a");
+        assert_eq!(
+            key(KeyCode::Enter, KeyModifiers::ALT).to_string(),
+            "A-Enter"
+        );
+        assert_eq!(
+            key(KeyCode::Delete, KeyModifiers::SUPER).to_string(),
+            "Super-Del"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_key_notation_serde() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Binding {
+            #[serde(with = "key_notation")]
+            key: KeyEvent,
+        }
+
+        let binding = Binding {
+            key: key(KeyCode::Char('x'), KeyModifiers::CONTROL),
+        };
+        let json = serde_json::to_string(&binding).unwrap();
+        assert_eq!(json, r#"{"key":"C-x"}"#);
+        assert_eq!(serde_json::from_str::<Binding>(&json).unwrap(), binding);
+        assert!(serde_json::from_str::<Binding>(r#"{"key":"C-Foo"}"#).is_err());
+    }
+}
@@ -0,0 +1,419 @@
+//! Routes the responses of the terminal to the queries waiting for them.
+//!
+//! A query registers itself before it's written to the terminal. Whichever thread reads the input
+//! passes the responses accepted by the oldest query waiting for them to it, so queries work while
+//! another thread is blocked in [`read`](super::read) or an [`EventStream`](super::EventStream) is
+//! alive, and the responses aren't reported as events.
+
+use std::{fs::File, io, sync::Arc, time::Duration};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::event::{
+    filter::{Filter, QueryCompletedFilter},
+    report_unknown_sequences,
+    timeout::PollTimeout,
+    try_lock_internal_event_reader_for, Event, InternalEvent, UnknownSequence,
+};
+
+/// The queries waiting for responses.
+static PENDING_QUERIES: Mutex<PendingQueries> = parking_lot::const_mutex(PendingQueries {
+    queries: Vec::new(),
+    owed_sentinels: 0,
+});
+
+/// Held while a query is registered and written, so that the queries are registered in the order
+/// the terminal answers them.
+static WRITE_LOCK: Mutex<()> = parking_lot::const_mutex(());
+
+/// How long a query reads the input at once, and how often it checks whether it needs to read the
+/// input while another thread does.
+const READER_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+// ESC [ c          Query primary device attributes (widely supported)
+const PRIMARY_DEVICE_ATTRIBUTES: &[u8] = b"\x1B[c";
+
+struct PendingQueries {
+    /// The queries, oldest first.
+    queries: Vec<PendingQuery>,
+    /// The number of primary device attributes the terminal still owes to sentinel queries which
+    /// timed out. They are dropped, so that they don't complete a later query early.
+    owed_sentinels: usize,
+}
+
+struct PendingQuery {
+    filter: Box<dyn Filter>,
+    sentinel: bool,
+    capture_responses: bool,
+    inbox: Arc<Inbox>,
+}
+
+/// The responses of a query, shared between the query and the thread reading them.
+#[derive(Default)]
+struct Inbox {
+    state: Mutex<InboxState>,
+    completed: Condvar,
+}
+
+#[derive(Default)]
+struct InboxState {
+    responses: Vec<InternalEvent>,
+    completed: bool,
+}
+
+impl Inbox {
+    fn push(&self, response: InternalEvent) {
+        self.state.lock().responses.push(response);
+    }
+
+    fn complete(&self) {
+        self.state.lock().completed = true;
+        self.completed.notify_all();
+    }
+
+    fn is_completed(&self) -> bool {
+        self.state.lock().completed
+    }
+
+    /// Waits until the query is completed or the timeout elapsed.
+    fn wait_for(&self, timeout: Duration) {
+        let mut state = self.state.lock();
+        if !state.completed {
+            self.completed.wait_for(&mut state, timeout);
+        }
+    }
+
+    fn take_responses(&self) -> Vec<InternalEvent> {
+        std::mem::take(&mut self.state.lock().responses)
+    }
+}
+
+/// The outcome of passing an event to the pending queries.
+pub(crate) enum Routed {
+    /// No query waits for the event.
+    Passed(InternalEvent),
+    /// The event is a response to a query.
+    Consumed,
+    /// The event is the last response to a query.
+    Completed,
+    /// The event is a response no query waits for.
+    Dropped,
+}
+
+/// Passes the event to the oldest pending query accepting it.
+///
+/// Queries with a sentinel are completed by the primary device attributes, other queries by
+/// their first response.
+pub(crate) fn route(event: InternalEvent) -> Routed {
+    match route_to(&mut PENDING_QUERIES.lock(), event) {
+        Routed::Passed(event) => pass_unsolicited(event, report_unknown_sequences()),
+        routed => routed,
+    }
+}
+
+/// Drops the responses which only queries read, such as late responses to a query which timed
+/// out, so that they don't pile up in the reader. Control strings are reported as unknown
+/// sequences instead if that is enabled.
+fn pass_unsolicited(event: InternalEvent, report_unknown_sequences: bool) -> Routed {
+    let (introducer, data) = match event {
+        InternalEvent::Event(_) => return Routed::Passed(event),
+        InternalEvent::OperatingSystemCommand(data) => (b']', data),
+        InternalEvent::DeviceControlString(data) => (b'P', data),
+        InternalEvent::ApplicationProgramCommand(data) => (b'_', data),
+        _ => return Routed::Dropped,
+    };
+    if !report_unknown_sequences {
+        return Routed::Dropped;
+    }
+    let mut bytes = vec![b'\x1B', introducer];
+    bytes.extend_from_slice(&data);
+    bytes.extend_from_slice(b"\x1B\\");
+    Routed::Passed(InternalEvent::Event(Event::Unknown(UnknownSequence::new(
+        &bytes,
+    ))))
+}
+
+fn route_to(pending: &mut PendingQueries, event: InternalEvent) -> Routed {
+    if pending.owed_sentinels > 0 && matches!(event, InternalEvent::PrimaryDeviceAttributes) {
+        pending.owed_sentinels -= 1;
+        return Routed::Dropped;
+    }
+    let queries = &mut pending.queries;
+    let index = match queries.iter().position(|query| query.filter.eval(&event)) {
+        Some(index) => index,
+        None => return Routed::Passed(event),
+    };
+    let query = &queries[index];
+    let is_sentinel = query.sentinel && matches!(event, InternalEvent::PrimaryDeviceAttributes);
+    if !is_sentinel {
+        query.inbox.push(event);
+    }
+    if query.sentinel && !is_sentinel {
+        return Routed::Consumed;
+    }
+    queries.remove(index).inbox.complete();
+    Routed::Completed
+}
+
+/// Returns whether control sequences which can't be parsed should be read as responses.
+pub(crate) fn is_capturing_responses() -> bool {
+    PENDING_QUERIES
+        .lock()
+        .queries
+        .iter()
+        .any(|query| query.capture_responses)
+}
+
+/// Returns whether a query waits for responses.
+pub(crate) fn has_pending_queries() -> bool {
+    !PENDING_QUERIES.lock().queries.is_empty()
+}
+
+/// Writes a query to the terminal.
+fn write_query(query: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    let result = File::open("/dev/tty").and_then(|mut file| {
+        file.write_all(query)?;
+        file.flush()
+    });
+    if result.is_err() {
+        let mut stdout = io::stdout();
+        stdout.write_all(query)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+fn timeout_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        "The terminal didn't respond to the query within the timeout",
+    )
+}
+
+/// Writes `request` to the terminal and returns the responses `filter` accepts.
+///
+/// With `sentinel`, the request is followed by a primary device attributes query, which all
+/// terminals answer, and all responses until the device attributes are returned. Otherwise the
+/// first response is returned. With `capture_responses`, control sequences crossterm doesn't parse
+/// are read as `InternalEvent::ControlSequence`.
+///
+/// If another thread reads the input, it passes the responses to the query. Otherwise the input
+/// is read until the query is completed, and the events which aren't responses are kept.
+pub(crate) fn send<F: Filter>(
+    request: &[u8],
+    filter: F,
+    sentinel: bool,
+    capture_responses: bool,
+    timeout: Duration,
+) -> io::Result<Vec<InternalEvent>> {
+    let timeout = PollTimeout::new(Some(timeout));
+    let inbox = Arc::new(Inbox::default());
+
+    {
+        let _write_lock = WRITE_LOCK.lock();
+        let mut query = request.to_vec();
+        if sentinel {
+            query.extend_from_slice(PRIMARY_DEVICE_ATTRIBUTES);
+        }
+        // Register the query before writing it, so that no response arrives before it's
+        // registered. The registry isn't locked while writing, so the input can still be read.
+        PENDING_QUERIES.lock().queries.push(PendingQuery {
+            filter: Box::new(filter),
+            sentinel,
+            capture_responses,
+            inbox: inbox.clone(),
+        });
+        if let Err(e) = write_query(&query) {
+            unregister(&inbox, false);
+            return Err(e);
+        }
+    }
+
+    let result = wait(&inbox, &timeout);
+    unregister(&inbox, sentinel);
+    result?;
+    Ok(inbox.take_responses())
+}
+
+/// Removes the query from the registry if it's still pending, which means it timed out. The
+/// terminal may still answer a timed out query, so its sentinel is owed then.
+fn unregister(inbox: &Arc<Inbox>, sentinel: bool) {
+    let mut pending = PENDING_QUERIES.lock();
+    let count = pending.queries.len();
+    pending
+        .queries
+        .retain(|query| !Arc::ptr_eq(&query.inbox, inbox));
+    if sentinel && pending.queries.len() < count {
+        pending.owed_sentinels += 1;
+    }
+}
+
+/// Waits until the query is completed, reading the input while no other thread reads it.
+fn wait(inbox: &Inbox, timeout: &PollTimeout) -> io::Result<()> {
+    loop {
+        if inbox.is_completed() {
+            return Ok(());
+        }
+        if timeout.elapsed() {
+            return Err(timeout_error());
+        }
+        let leftover = timeout.leftover().unwrap_or_default();
+
+        match try_lock_internal_event_reader_for(Duration::ZERO) {
+            Some(mut reader) => {
+                // Hold the reader only briefly, so that another thread reading the events doesn't
+                // stall.
+                let timeout = leftover.min(READER_RETRY_INTERVAL);
+                if reader.poll(Some(timeout), &QueryCompletedFilter)? {
+                    reader.read(&QueryCompletedFilter)?;
+                }
+            }
+            // Another thread reads the input and passes the responses to the query.
+            None => inbox.wait_for(leftover.min(READER_RETRY_INTERVAL)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{pass_unsolicited, route_to, Inbox, PendingQueries, PendingQuery, Routed};
+    use crate::event::{
+        filter::{CursorPositionFilter, Filter, QueryResponseFilter},
+        Event, InternalEvent,
+    };
+
+    fn pending_query<F: Filter>(filter: F, sentinel: bool) -> (PendingQuery, Arc<Inbox>) {
+        let inbox = Arc::new(Inbox::default());
+        let query = PendingQuery {
+            filter: Box::new(filter),
+            sentinel,
+            capture_responses: false,
+            inbox: inbox.clone(),
+        };
+        (query, inbox)
+    }
+
+    fn pending_queries(queries: Vec<PendingQuery>) -> PendingQueries {
+        PendingQueries {
+            queries,
+            owed_sentinels: 0,
+        }
+    }
+
+    #[test]
+    fn test_route_until_sentinel() {
+        let (query, inbox) = pending_query(QueryResponseFilter, true);
+        let mut queries = pending_queries(vec![query]);
+
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::Event(Event::FocusGained)),
+            Routed::Passed(InternalEvent::Event(Event::FocusGained))
+        ));
+        assert!(matches!(
+            route_to(
+                &mut queries,
+                InternalEvent::OperatingSystemCommand(b"11;rgb:0/0/0".to_vec())
+            ),
+            Routed::Consumed
+        ));
+        assert!(!inbox.is_completed());
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::PrimaryDeviceAttributes),
+            Routed::Completed
+        ));
+        assert!(inbox.is_completed());
+        assert!(queries.queries.is_empty());
+        assert!(matches!(
+            inbox.take_responses()[..],
+            [InternalEvent::OperatingSystemCommand(_)]
+        ));
+
+        // Without a pending query, responses are passed on.
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::PrimaryDeviceAttributes),
+            Routed::Passed(InternalEvent::PrimaryDeviceAttributes)
+        ));
+    }
+
+    #[test]
+    fn test_route_to_oldest_accepting_query() {
+        let (cursor_query, cursor_inbox) = pending_query(CursorPositionFilter, false);
+        let (first_query, first_inbox) = pending_query(QueryResponseFilter, true);
+        let (second_query, second_inbox) = pending_query(QueryResponseFilter, true);
+        let mut queries = pending_queries(vec![cursor_query, first_query, second_query]);
+
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::PrimaryDeviceAttributes),
+            Routed::Completed
+        ));
+        assert!(first_inbox.is_completed());
+        assert!(!second_inbox.is_completed());
+
+        // A query without a sentinel is completed by its first response.
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::CursorPosition(1, 2)),
+            Routed::Completed
+        ));
+        assert!(cursor_inbox.is_completed());
+        assert!(matches!(
+            cursor_inbox.take_responses()[..],
+            [InternalEvent::CursorPosition(1, 2)]
+        ));
+        assert_eq!(queries.queries.len(), 1);
+    }
+
+    #[test]
+    fn test_route_owed_sentinels() {
+        let (query, inbox) = pending_query(QueryResponseFilter, true);
+        let mut queries = pending_queries(vec![query]);
+        // The sentinel of a query which timed out completes no later query.
+        queries.owed_sentinels = 1;
+
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::PrimaryDeviceAttributes),
+            Routed::Dropped
+        ));
+        assert!(!inbox.is_completed());
+        assert_eq!(queries.owed_sentinels, 0);
+        assert!(matches!(
+            route_to(&mut queries, InternalEvent::PrimaryDeviceAttributes),
+            Routed::Completed
+        ));
+        assert!(inbox.is_completed());
+    }
+
+    #[test]
+    fn test_pass_unsolicited() {
+        let osc = || InternalEvent::OperatingSystemCommand(b"11;rgb:0/0/0".to_vec());
+        assert!(matches!(pass_unsolicited(osc(), false), Routed::Dropped));
+        match pass_unsolicited(osc(), true) {
+            Routed::Passed(InternalEvent::Event(Event::Unknown(sequence))) => {
+                assert_eq!(sequence.bytes(), b"\x1B]11;rgb:0/0/0\x1B\\");
+            }
+            _ => panic!("expected an unknown sequence"),
+        }
+        assert!(matches!(
+            pass_unsolicited(
+                InternalEvent::SecondaryDeviceAttributes(Default::default()),
+                true
+            ),
+            Routed::Dropped
+        ));
+        assert!(matches!(
+            pass_unsolicited(InternalEvent::CursorPosition(1, 2), true),
+            Routed::Dropped
+        ));
+        assert!(matches!(
+            pass_unsolicited(InternalEvent::PrimaryDeviceAttributes, true),
+            Routed::Dropped
+        ));
+        assert!(matches!(
+            pass_unsolicited(InternalEvent::Event(Event::FocusGained), false),
+            Routed::Passed(InternalEvent::Event(Event::FocusGained))
+        ));
+    }
+}
@@ -1,18 +1,102 @@
-use std::{collections::vec_deque::VecDeque, io, time::Duration};
+use std::{
+    collections::vec_deque::VecDeque,
+    io,
+    time::{Duration, Instant},
+};
 
+#[cfg(unix)]
+use crate::event::query::{self, Routed};
 #[cfg(unix)]
 use crate::event::source::unix::UnixInternalEventSource;
 #[cfg(windows)]
 use crate::event::source::windows::WindowsEventSource;
 #[cfg(feature = "event-stream")]
 use crate::event::sys::Waker;
-use crate::event::{filter::Filter, source::EventSource, timeout::PollTimeout, InternalEvent};
+use crate::event::{
+    filter::Filter, source::EventSource, timeout::PollTimeout, Event, InternalEvent,
+    RESIZE_COALESCING,
+};
+use parking_lot::Mutex;
+
+/// Holds back resize events to collapse them to the latest one.
+#[derive(Debug)]
+struct ResizeCoalescer {
+    // The debounce, which is read for every event since another thread may change it.
+    debounce: &'static Mutex<Option<Duration>>,
+    // The latest held back resize event and when it is due.
+    pending: Option<(Event, Instant)>,
+}
+
+impl Default for ResizeCoalescer {
+    fn default() -> Self {
+        ResizeCoalescer {
+            debounce: &RESIZE_COALESCING,
+            pending: None,
+        }
+    }
+}
+
+impl ResizeCoalescer {
+    /// Holds back the event if it's a resize event and coalescing is enabled.
+    ///
+    /// Returns the event if it wasn't held back.
+    fn hold(&mut self, event: InternalEvent) -> Option<InternalEvent> {
+        match (*self.debounce.lock(), event) {
+            (
+                Some(debounce),
+                InternalEvent::Event(event @ (Event::Resize(..) | Event::WindowResize(_))),
+            ) => {
+                // Every resize restarts the debounce.
+                self.pending = Some((event, Instant::now() + debounce));
+                None
+            }
+            (_, event) => Some(event),
+        }
+    }
+
+    /// Shortens the given poll timeout so that polling returns when the held back resize is due.
+    fn shorten_timeout(&self, timeout: Option<Duration>) -> Option<Duration> {
+        match self.due() {
+            Some(due) => {
+                let left = due.saturating_duration_since(Instant::now());
+                Some(timeout.map_or(left, |timeout| timeout.min(left)))
+            }
+            None => timeout,
+        }
+    }
+
+    /// Takes the held back resize, if there is one.
+    fn take(&mut self) -> Option<InternalEvent> {
+        let (event, _) = self.pending.take()?;
+        Some(InternalEvent::Event(event))
+    }
+
+    /// Takes the held back resize if its debounce has elapsed.
+    fn take_due(&mut self) -> Option<InternalEvent> {
+        match self.due() {
+            Some(due) if due <= Instant::now() => self.take(),
+            _ => None,
+        }
+    }
+
+    /// Returns when the held back resize is due, if there is one.
+    fn due(&self) -> Option<Instant> {
+        let (_, due) = self.pending.as_ref()?;
+        // A resize held back before coalescing was disabled is due right away.
+        if self.debounce.lock().is_some() {
+            Some(*due)
+        } else {
+            Some(Instant::now())
+        }
+    }
+}
 
 /// Can be used to read `InternalEvent`s.
 pub(crate) struct InternalEventReader {
     events: VecDeque<InternalEvent>,
     source: Option<Box<dyn EventSource>>,
     skipped_events: Vec<InternalEvent>,
+    resizes: ResizeCoalescer,
 }
 
 impl Default for InternalEventReader {
@@ -28,6 +112,7 @@ impl Default for InternalEventReader {
             source,
             events: VecDeque::with_capacity(32),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         }
     }
 }
@@ -62,16 +147,29 @@ impl InternalEventReader {
         let poll_timeout = PollTimeout::new(timeout);
 
         loop {
-            let maybe_event = match event_source.try_read(poll_timeout.leftover()) {
-                Ok(None) => None,
-                Ok(Some(event)) => {
-                    if filter.eval(&event) {
-                        Some(event)
-                    } else {
-                        self.skipped_events.push(event);
-                        None
+            let timeout = self.resizes.shorten_timeout(poll_timeout.leftover());
+            let events = match event_source.try_read(timeout) {
+                Ok(None) => [self.resizes.take_due(), None],
+                #[cfg(unix)]
+                Ok(Some(event)) => match query::route(event) {
+                    Routed::Passed(event) => match self.resizes.hold(event) {
+                        None => continue,
+                        Some(event) => [self.resizes.take(), Some(event)],
+                    },
+                    Routed::Consumed | Routed::Dropped => continue,
+                    // Let a query polling for its completion return.
+                    Routed::Completed if filter.eval(&InternalEvent::QueryCompleted) => {
+                        [None, Some(InternalEvent::QueryCompleted)]
                     }
-                }
+                    Routed::Completed => continue,
+                },
+                #[cfg(not(unix))]
+                Ok(Some(event)) => match self.resizes.hold(event) {
+                    // Keep reading the events which are ready, to collapse queued resizes.
+                    None => continue,
+                    // A held back resize happened before this event, so it goes first.
+                    Some(event) => [self.resizes.take(), Some(event)],
+                },
                 Err(e) => {
                     if e.kind() == io::ErrorKind::Interrupted {
                         return Ok(false);
@@ -81,6 +179,15 @@ impl InternalEventReader {
                 }
             };
 
+            let mut maybe_event = None;
+            for event in events.into_iter().flatten() {
+                if maybe_event.is_none() && filter.eval(&event) {
+                    maybe_event = Some(event);
+                } else {
+                    self.skipped_events.push(event);
+                }
+            }
+
             if poll_timeout.elapsed() || maybe_event.is_some() {
                 self.events.extend(self.skipped_events.drain(..));
 
@@ -132,7 +239,11 @@ mod tests {
 
     #[cfg(unix)]
     use super::super::filter::CursorPositionFilter;
-    use super::{super::Event, EventSource, Filter, InternalEvent, InternalEventReader};
+    use super::{
+        super::Event, EventSource, Filter, InternalEvent, InternalEventReader, ResizeCoalescer,
+    };
+    use crate::terminal::WindowSize;
+    use parking_lot::Mutex;
 
     #[derive(Debug, Clone)]
     pub(crate) struct InternalEventFilter;
@@ -149,6 +260,7 @@ mod tests {
             events: VecDeque::new(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert!(reader.poll(None, &InternalEventFilter).is_err());
@@ -166,6 +278,7 @@ mod tests {
             events: vec![InternalEvent::Event(Event::Resize(10, 10))].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert!(reader.poll(None, &InternalEventFilter).unwrap());
@@ -182,6 +295,7 @@ mod tests {
             .into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert!(reader.poll(None, &CursorPositionFilter).unwrap());
@@ -195,6 +309,7 @@ mod tests {
             events: vec![EVENT].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -209,6 +324,7 @@ mod tests {
             events: vec![InternalEvent::Event(Event::Resize(10, 10)), CURSOR_EVENT].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&CursorPositionFilter).unwrap(), CURSOR_EVENT);
@@ -224,6 +340,7 @@ mod tests {
             events: vec![SKIPPED_EVENT, CURSOR_EVENT].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&CursorPositionFilter).unwrap(), CURSOR_EVENT);
@@ -238,6 +355,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert!(!reader
@@ -253,6 +371,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert!(reader.poll(None, &InternalEventFilter).unwrap());
@@ -271,6 +390,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -286,6 +406,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -303,6 +424,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -319,6 +441,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(FakeSource::new(&[]))),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(
@@ -336,6 +459,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(FakeSource::new(&[]))),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(
@@ -357,6 +481,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -376,6 +501,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer::default(),
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -383,6 +509,99 @@ mod tests {
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
     }
 
+    fn resize(columns: u16, rows: u16) -> InternalEvent {
+        InternalEvent::Event(Event::Resize(columns, rows))
+    }
+
+    fn window_resize(columns: u16, rows: u16) -> InternalEvent {
+        InternalEvent::Event(Event::WindowResize(WindowSize {
+            rows,
+            columns,
+            width: columns * 10,
+            height: rows * 20,
+        }))
+    }
+
+    fn coalescing_reader(
+        source: FakeSource,
+        debounce: Duration,
+    ) -> (InternalEventReader, &'static Mutex<Option<Duration>>) {
+        // Each test has its own setting, since they run in parallel.
+        let debounce = Box::leak(Box::new(Mutex::new(Some(debounce))));
+        let reader = InternalEventReader {
+            events: VecDeque::new(),
+            source: Some(Box::new(source)),
+            skipped_events: Vec::with_capacity(32),
+            resizes: ResizeCoalescer {
+                debounce,
+                pending: None,
+            },
+        };
+        (reader, debounce)
+    }
+
+    #[test]
+    fn test_resize_coalescing_collapses_queued_resizes() {
+        let source = FakeSource::with_events(&[resize(80, 24), resize(100, 30), resize(120, 40)]);
+        let (mut reader, _) = coalescing_reader(source, Duration::ZERO);
+
+        assert!(reader
+            .poll(Some(Duration::ZERO), &InternalEventFilter)
+            .unwrap());
+        assert_eq!(reader.read(&InternalEventFilter).unwrap(), resize(120, 40));
+        assert!(!reader
+            .poll(Some(Duration::ZERO), &InternalEventFilter)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_resize_coalescing_collapses_in_band_resizes() {
+        let source = FakeSource::with_events(&[window_resize(80, 24), window_resize(100, 30)]);
+        let (mut reader, _) = coalescing_reader(source, Duration::ZERO);
+
+        assert_eq!(
+            reader.read(&InternalEventFilter).unwrap(),
+            window_resize(100, 30)
+        );
+    }
+
+    #[test]
+    fn test_resize_coalescing_debounce() {
+        let source = FakeSource::with_events(&[resize(80, 24)]);
+        let (mut reader, _) = coalescing_reader(source, Duration::from_millis(50));
+
+        assert!(!reader
+            .poll(Some(Duration::ZERO), &InternalEventFilter)
+            .unwrap());
+        assert!(reader
+            .poll(Some(Duration::from_secs(10)), &InternalEventFilter)
+            .unwrap());
+        assert_eq!(reader.read(&InternalEventFilter).unwrap(), resize(80, 24));
+    }
+
+    #[test]
+    fn test_resize_coalescing_keeps_event_order() {
+        const EVENT: InternalEvent = InternalEvent::Event(Event::FocusGained);
+
+        let source = FakeSource::with_events(&[resize(80, 24), resize(100, 30), EVENT]);
+        let (mut reader, _) = coalescing_reader(source, Duration::from_secs(3600));
+
+        assert_eq!(reader.read(&InternalEventFilter).unwrap(), resize(100, 30));
+        assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
+    }
+
+    #[test]
+    fn test_disabling_resize_coalescing_reports_held_resize() {
+        let source = FakeSource::with_events(&[resize(80, 24)]);
+        let (mut reader, debounce) = coalescing_reader(source, Duration::from_secs(3600));
+
+        assert!(!reader
+            .poll(Some(Duration::ZERO), &InternalEventFilter)
+            .unwrap());
+        *debounce.lock() = None;
+        assert_eq!(reader.read(&InternalEventFilter).unwrap(), resize(80, 24));
+    }
+
     #[derive(Default)]
     struct FakeSource {
         events: VecDeque<InternalEvent>,
@@ -1,3 +1,22 @@
+use std::{io, os::raw::c_int, os::unix::net::UnixStream};
+
+use parking_lot::Mutex;
+use signal_hook::consts::{FORBIDDEN, SIGCONT, SIGWINCH};
+use signal_hook::iterator::{
+    backend::{Handle, SignalDelivery},
+    exfiltrator::SignalOnly,
+};
+
+#[cfg(feature = "bracketed-paste")]
+use crate::event::paste_options;
+use crate::event::{
+    escape_timeout, mouse_encoding, query, report_unknown_sequences, Event, InternalEvent,
+};
+
+use self::parser::{Parser, ResizeSource};
+
+pub(crate) mod parser;
+
 #[cfg(feature = "use-dev-tty")]
 pub(crate) mod tty;
 
@@ -9,3 +28,130 @@ pub(crate) use self::tty::UnixInternalEventSource;
 
 #[cfg(not(feature = "use-dev-tty"))]
 pub(crate) use self::mio::UnixInternalEventSource;
+
+/// The signals the event sources always report as events. The others are reported once
+/// registered, see `register_signals`.
+pub(crate) const SIGNALS: [c_int; 1] = [SIGWINCH];
+
+/// The handle to register signals with the event source, set once the source is created.
+static SIGNAL_HANDLE: Mutex<Option<Handle>> = parking_lot::const_mutex(None);
+
+/// Creates the delivery of the signals to the event source through a nonblocking pipe.
+pub(crate) fn signal_delivery() -> io::Result<SignalDelivery<UnixStream, SignalOnly>> {
+    let (receiver, sender) = nonblocking_unix_pair()?;
+    // Unregistering is unnecessary because EventSource is a singleton
+    let signals = SignalDelivery::with_pipe(receiver, sender, SignalOnly, SIGNALS)?;
+    *SIGNAL_HANDLE.lock() = Some(signals.handle());
+    Ok(signals)
+}
+
+pub(crate) fn nonblocking_unix_pair() -> io::Result<(UnixStream, UnixStream)> {
+    let (receiver, sender) = UnixStream::pair()?;
+    receiver.set_nonblocking(true)?;
+    sender.set_nonblocking(true)?;
+    Ok((receiver, sender))
+}
+
+/// Returns whether an event source was created to register signals with.
+pub(crate) fn has_signal_handle() -> bool {
+    SIGNAL_HANDLE.lock().is_some()
+}
+
+/// Registers signals with the event source, which reports them as events.
+///
+/// The handlers are installed by the calling thread, so this doesn't wait for a thread blocked
+/// reading events.
+pub(crate) fn register_signals(signals: &[c_int]) -> io::Result<()> {
+    let handle = SIGNAL_HANDLE
+        .lock()
+        .clone()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Failed to initialize input reader"))?;
+    for &signal in signals {
+        check_signal(signal)?;
+        handle.add_signal(signal)?;
+    }
+    Ok(())
+}
+
+/// Feeds input to the parser with the current settings, which may have been changed by another
+/// thread since the last input.
+pub(crate) fn advance_parser(parser: &mut Parser, buffer: &[u8], more: bool) {
+    parser.set_escape_timeout(escape_timeout());
+    parser.set_report_unknown_sequences(report_unknown_sequences());
+    parser.set_mouse_encoding(mouse_encoding());
+    #[cfg(feature = "bracketed-paste")]
+    parser.set_paste_options(paste_options());
+    // Read the responses to pending queries which crossterm doesn't parse.
+    parser.set_capture_responses(query::is_capturing_responses());
+    parser.set_awaiting_responses(query::has_pending_queries());
+    parser.advance(buffer, more);
+}
+
+/// Checks that a signal can be registered, since signal-hook panics for the others.
+pub(crate) fn check_signal(signal: c_int) -> io::Result<()> {
+    // signal-hook supports signal numbers up to 127.
+    if !(1..128).contains(&signal) || FORBIDDEN.contains(&signal) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The signal {} can't be registered", signal),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the event to report for a received signal, if any.
+pub(crate) fn signal_event(
+    signal: c_int,
+    parser: &mut Parser,
+) -> io::Result<Option<InternalEvent>> {
+    match signal {
+        SIGWINCH => {
+            // TODO Should we remove tput?
+            //
+            // This can take a really long time, because terminal::size can
+            // launch new process (tput) and then it parses its output. It's
+            // not a really long time from the absolute time point of view, but
+            // it's a really long time from the mio, async-std/tokio executor, ...
+            // point of view.
+            let new_size = crate::terminal::size()?;
+            // The terminal may report the new size in-band as well.
+            if parser.is_duplicate_resize(ResizeSource::Signal, new_size) {
+                return Ok(None);
+            }
+            Ok(Some(InternalEvent::Event(Event::Resize(
+                new_size.0, new_size.1,
+            ))))
+        }
+        SIGCONT => Ok(Some(InternalEvent::Event(Event::Resumed))),
+        signal => Ok(Some(InternalEvent::Event(Event::Signal(signal)))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_event() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            signal_event(SIGCONT, &mut parser).unwrap(),
+            Some(InternalEvent::Event(Event::Resumed))
+        );
+        assert_eq!(
+            signal_event(signal_hook::consts::SIGINT, &mut parser).unwrap(),
+            Some(InternalEvent::Event(Event::Signal(
+                signal_hook::consts::SIGINT
+            )))
+        );
+    }
+
+    #[test]
+    fn test_check_signal() {
+        assert!(check_signal(signal_hook::consts::SIGTERM).is_ok());
+        assert!(check_signal(signal_hook::consts::SIGKILL).is_err());
+        assert!(check_signal(0).is_err());
+        assert!(check_signal(-1).is_err());
+        assert!(check_signal(1000).is_err());
+    }
+}
@@ -1,15 +1,16 @@
+use std::os::unix::{io::AsRawFd, net::UnixStream};
 use std::{collections::VecDeque, io, time::Duration};
 
 use mio::{unix::SourceFd, Events, Interest, Poll, Token};
-use signal_hook_mio::v1_0::Signals;
+use signal_hook::iterator::{backend::SignalDelivery, exfiltrator::SignalOnly};
 
 #[cfg(feature = "event-stream")]
 use crate::event::sys::Waker;
-use crate::event::{
-    source::EventSource, sys::unix::parse::parse_event, timeout::PollTimeout, Event, InternalEvent,
-};
+use crate::event::{source::EventSource, timeout::PollTimeout, InternalEvent};
 use crate::terminal::sys::file_descriptor::{tty_fd, FileDesc};
 
+use super::{advance_parser, parser::Parser, signal_delivery, signal_event};
+
 // Tokens to identify file descriptor
 const TTY_TOKEN: Token = Token(0);
 const SIGNAL_TOKEN: Token = Token(1);
@@ -27,7 +28,8 @@ pub(crate) struct UnixInternalEventSource {
     parser: Parser,
     tty_buffer: [u8; TTY_BUFFER_SIZE],
     tty_fd: FileDesc<'static>,
-    signals: Signals,
+    signals: SignalDelivery<UnixStream, SignalOnly>,
+    signal_events: VecDeque<InternalEvent>,
     #[cfg(feature = "event-stream")]
     waker: Waker,
 }
@@ -45,8 +47,10 @@ impl UnixInternalEventSource {
         let mut tty_ev = SourceFd(&tty_raw_fd);
         registry.register(&mut tty_ev, TTY_TOKEN, Interest::READABLE)?;
 
-        let mut signals = Signals::new([signal_hook::consts::SIGWINCH])?;
-        registry.register(&mut signals, SIGNAL_TOKEN, Interest::READABLE)?;
+        let signals = signal_delivery()?;
+        let signals_raw_fd = signals.get_read().as_raw_fd();
+        let mut signals_ev = SourceFd(&signals_raw_fd);
+        registry.register(&mut signals_ev, SIGNAL_TOKEN, Interest::READABLE)?;
 
         #[cfg(feature = "event-stream")]
         let waker = Waker::new(registry, WAKE_TOKEN)?;
@@ -58,6 +62,7 @@ impl UnixInternalEventSource {
             tty_buffer: [0u8; TTY_BUFFER_SIZE],
             tty_fd: input_fd,
             signals,
+            signal_events: VecDeque::new(),
             #[cfg(feature = "event-stream")]
             waker,
         })
@@ -69,11 +74,15 @@ impl EventSource for UnixInternalEventSource {
         if let Some(event) = self.parser.next() {
             return Ok(Some(event));
         }
+        if let Some(event) = self.signal_events.pop_front() {
+            return Ok(Some(event));
+        }
 
         let timeout = PollTimeout::new(timeout);
 
         loop {
-            if let Err(e) = self.poll.poll(&mut self.events, timeout.leftover()) {
+            let poll_timeout = self.parser.limit_timeout(timeout.leftover());
+            if let Err(e) = self.poll.poll(&mut self.events, poll_timeout) {
                 // Mio will throw an interrupted error in case of cursor position retrieval. We need to retry until it succeeds.
                 // Previous versions of Mio (< 0.7) would automatically retry the poll call if it was interrupted (if EINTR was returned).
                 // https://docs.rs/mio/0.7.0/mio/struct.Poll.html#notes
@@ -85,8 +94,16 @@ impl EventSource for UnixInternalEventSource {
             };
 
             if self.events.is_empty() {
-                // No readiness events = timeout
-                return Ok(None);
+                // No readiness events = timeout, either ours or the escape timeout of an
+                // incomplete sequence.
+                self.parser.flush_expired();
+                if let Some(event) = self.parser.next() {
+                    return Ok(Some(event));
+                }
+                if timeout.elapsed() {
+                    return Ok(None);
+                }
+                continue;
             }
 
             for token in self.events.iter().map(|x| x.token()) {
@@ -96,11 +113,19 @@ impl EventSource for UnixInternalEventSource {
                             match self.tty_fd.read(&mut self.tty_buffer) {
                                 Ok(read_count) => {
                                     if read_count > 0 {
-                                        self.parser.advance(
+                                        advance_parser(
+                                            &mut self.parser,
                                             &self.tty_buffer[..read_count],
                                             read_count == TTY_BUFFER_SIZE,
                                         );
                                     }
+                                    // All available input was read. Don't block on reading the
+                                    // rest of an incomplete sequence, polling again respects the
+                                    // escape timeout. Until then, keep reading, since mio only
+                                    // reports input which arrives after the unread input.
+                                    if read_count < TTY_BUFFER_SIZE {
+                                        break;
+                                    }
                                 }
                                 Err(e) => {
                                     // No more data to read at the moment. We will receive another event
@@ -113,25 +138,20 @@ impl EventSource for UnixInternalEventSource {
                                     }
                                 }
                             };
+                        }
 
-                            if let Some(event) = self.parser.next() {
-                                return Ok(Some(event));
-                            }
+                        if let Some(event) = self.parser.next() {
+                            return Ok(Some(event));
                         }
                     }
                     SIGNAL_TOKEN => {
-                        if self.signals.pending().next() == Some(signal_hook::consts::SIGWINCH) {
-                            // TODO Should we remove tput?
-                            //
-                            // This can take a really long time, because terminal::size can
-                            // launch new process (tput) and then it parses its output. It's
-                            // not a really long time from the absolute time point of view, but
-                            // it's a really long time from the mio, async-std/tokio executor, ...
-                            // point of view.
-                            let new_size = crate::terminal::size()?;
-                            return Ok(Some(InternalEvent::Event(Event::Resize(
-                                new_size.0, new_size.1,
-                            ))));
+                        for signal in self.signals.pending() {
+                            if let Some(event) = signal_event(signal, &mut self.parser)? {
+                                self.signal_events.push_back(event);
+                            }
+                        }
+                        if let Some(event) = self.signal_events.pop_front() {
+                            return Ok(Some(event));
                         }
                     }
                     #[cfg(feature = "event-stream")]
@@ -157,73 +177,3 @@ impl EventSource for UnixInternalEventSource {
         self.waker.clone()
     }
 }
-
-//
-// Following `Parser` structure exists for two reasons:
-//
-//  * mimic anes Parser interface
-//  * move the advancing, parsing, ... stuff out of the `try_read` method
-//
-#[derive(Debug)]
-struct Parser {
-    buffer: Vec<u8>,
-    internal_events: VecDeque<InternalEvent>,
-}
-
-impl Default for Parser {
-    fn default() -> Self {
-        Parser {
-            // This buffer is used for -> 1 <- ANSI escape sequence. Are we
-            // aware of any ANSI escape sequence that is bigger? Can we make
-            // it smaller?
-            //
-            // Probably not worth spending more time on this as "there's a plan"
-            // to use the anes crate parser.
-            buffer: Vec::with_capacity(256),
-            // TTY_BUFFER_SIZE is 1_024 bytes. How many ANSI escape sequences can
-            // fit? What is an average sequence length? Let's guess here
-            // and say that the average ANSI escape sequence length is 8 bytes. Thus
-            // the buffer size should be 1024/8=128 to avoid additional allocations
-            // when processing large amounts of data.
-            //
-            // There's no need to make it bigger, because when you look at the `try_read`
-            // method implementation, all events are consumed before the next TTY_BUFFER
-            // is processed -> events pushed.
-            internal_events: VecDeque::with_capacity(128),
-        }
-    }
-}
-
-impl Parser {
-    fn advance(&mut self, buffer: &[u8], more: bool) {
-        for (idx, byte) in buffer.iter().enumerate() {
-            let more = idx + 1 < buffer.len() || more;
-
-            self.buffer.push(*byte);
-
-            match parse_event(&self.buffer, more) {
-                Ok(Some(ie)) => {
-                    self.internal_events.push_back(ie);
-                    self.buffer.clear();
-                }
-                Ok(None) => {
-                    // Event can't be parsed, because we don't have enough bytes for
-                    // the current sequence. Keep the buffer and process next bytes.
-                }
-                Err(_) => {
-                    // Event can't be parsed (not enough parameters, parameter is not a number, ...).
-                    // Clear the buffer and continue with another sequence.
-                    self.buffer.clear();
-                }
-            }
-        }
-    }
-}
-
-impl Iterator for Parser {
-    type Item = InternalEvent;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.internal_events.pop_front()
-    }
-}
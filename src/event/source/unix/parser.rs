@@ -0,0 +1,1277 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::event::{
+    sys::unix::parse::{parse_csi_sequence, parse_csi_utf8_mouse, parse_event},
+    Event, InternalEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEncoding,
+    MousePositionUnit, UnknownSequence,
+};
+#[cfg(feature = "bracketed-paste")]
+use crate::event::{PasteData, PasteOptions};
+
+// The initial capacity of the sequence buffer. Bracketed pastes can grow the buffer far beyond
+// this, in which case it's shrunk back once the paste has been dispatched.
+const BUFFER_CAPACITY: usize = 256;
+
+// The maximum length of an OSC, DCS or APC string while no query is waiting for a response. The
+// rest of a longer string is dropped, since it can't be a response crossterm reads.
+const CONTROL_STRING_MAX_LEN: usize = 4096;
+
+#[cfg(feature = "bracketed-paste")]
+const PASTE_END: &[u8] = b"\x1B[201~";
+
+/// The state of the input [`Parser`].
+///
+/// The states loosely follow the DEC ANSI parser model (<https://vt100.net/emu/dec_ansi_parser>)
+/// trimmed down to the sequences a terminal sends as input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Waiting for the first byte of a new sequence.
+    Ground,
+    /// Reading a multi-byte UTF-8 character, with the number of continuation bytes still expected.
+    Utf8(u8),
+    /// `ESC` was read.
+    Escape,
+    /// `ESC O` was read.
+    Ss3,
+    /// `ESC [` was read.
+    CsiEntry,
+    /// Reading the parameter and intermediate bytes of a CSI sequence.
+    CsiParam,
+    /// `ESC [ [` was read (Linux console function keys).
+    CsiLinuxFunctionKey,
+    /// `ESC [ M` was read (normal mouse encoding), with the number of bytes still expected.
+    NormalMouse(u8),
+    /// Reading the data of an OSC (`ESC ]`), DCS (`ESC P`) or APC (`ESC _`) string.
+    ControlString,
+    /// `ESC` was read inside a control string, which is either the start of the string
+    /// terminator (`ESC \`) or cancels the string.
+    ControlStringEscape,
+    /// Dropping the rest of a control string which exceeded `CONTROL_STRING_MAX_LEN`.
+    OverlongControlString,
+    /// `ESC` was read inside an overlong control string.
+    OverlongControlStringEscape,
+    /// Reading the content of a bracketed paste.
+    #[cfg(feature = "bracketed-paste")]
+    Paste,
+}
+
+/// Where a resize was reported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResizeSource {
+    /// An in-band resize notification, see `EnableInBandResize`.
+    InBand,
+    /// `SIGWINCH`.
+    Signal,
+}
+
+/// A streaming parser turning terminal input bytes into `InternalEvent`s.
+///
+/// Each input byte is looked at once to find the end of the current sequence. A complete
+/// sequence is then handed to the `parse_*` functions exactly once, so the cost of parsing is
+/// linear in the size of the input, even for huge bracketed pastes.
+#[derive(Debug)]
+pub(crate) struct Parser {
+    state: State,
+    buffer: Vec<u8>,
+    internal_events: VecDeque<InternalEvent>,
+    escape_timeout: Option<Duration>,
+    // When the incomplete sequence in `buffer` is flushed if no more input arrives.
+    escape_deadline: Option<Instant>,
+    report_unknown_sequences: bool,
+    capture_responses: bool,
+    // Whether a query waits for responses, so that control strings aren't taken as keys.
+    awaiting_responses: bool,
+    mouse_encoding: MouseEncoding,
+    // The last key pressed in the win32-input-mode, which sends repeats as further presses.
+    win32_pressed_key: Option<KeyCode>,
+    // The last resize which wasn't a duplicate, and whether it was reported in-band.
+    last_resize: Option<(ResizeSource, (u16, u16))>,
+    #[cfg(feature = "bracketed-paste")]
+    paste_options: PasteOptions,
+    #[cfg(feature = "bracketed-paste")]
+    paste: PasteProgress,
+}
+
+/// The progress of the bracketed paste being read. Its content is kept in the parser's buffer.
+#[cfg(feature = "bracketed-paste")]
+#[derive(Debug, Default)]
+struct PasteProgress {
+    // How many bytes of `PASTE_END` were read. They're only content if the rest doesn't follow.
+    end_matched: usize,
+    // The size of the content read so far, including already streamed chunks.
+    size: usize,
+    truncated: bool,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            state: State::Ground,
+            buffer: Vec::with_capacity(BUFFER_CAPACITY),
+            // TTY_BUFFER_SIZE is 1_024 bytes. How many ANSI escape sequences can
+            // fit? What is an average sequence length? Let's guess here
+            // and say that the average ANSI escape sequence length is 8 bytes. Thus
+            // the buffer size should be 1024/8=128 to avoid additional allocations
+            // when processing large amounts of data.
+            //
+            // There's no need to make it bigger, because when you look at the `try_read`
+            // method implementation, all events are consumed before the next TTY_BUFFER
+            // is processed -> events pushed.
+            internal_events: VecDeque::with_capacity(128),
+            escape_timeout: None,
+            escape_deadline: None,
+            report_unknown_sequences: false,
+            capture_responses: false,
+            awaiting_responses: false,
+            mouse_encoding: MouseEncoding::Sgr,
+            win32_pressed_key: None,
+            last_resize: None,
+            #[cfg(feature = "bracketed-paste")]
+            paste_options: PasteOptions::default(),
+            #[cfg(feature = "bracketed-paste")]
+            paste: PasteProgress::default(),
+        }
+    }
+}
+
+impl Parser {
+    /// Feeds the given bytes to the parser.
+    ///
+    /// `more` tells whether more input is immediately available. Without an escape timeout, it's
+    /// used to decide whether a lone `ESC` at the end of the input is the Esc key or the start of
+    /// an escape sequence.
+    pub(crate) fn advance(&mut self, buffer: &[u8], more: bool) {
+        for byte in buffer {
+            self.advance_byte(*byte);
+        }
+
+        // Without an escape timeout, an `ESC` or an unterminated OSC, DCS or APC string at the
+        // end of the input is taken as keys if no more input is available.
+        if !more && self.escape_timeout.is_none() && self.is_key_prefix() {
+            self.flush_key_prefix();
+        }
+
+        #[cfg(feature = "bracketed-paste")]
+        if self.state == State::Paste && self.paste_options.streaming {
+            self.flush_paste_chunk(false);
+        }
+
+        self.escape_deadline = match self.escape_timeout {
+            Some(timeout) if self.is_pending() => Some(Instant::now() + timeout),
+            _ => None,
+        };
+    }
+
+    /// Sets how long an incomplete sequence is kept waiting for more input.
+    ///
+    /// `None` restores the default of deciding by the `more` flag passed to [`Parser::advance`].
+    pub(crate) fn set_escape_timeout(&mut self, timeout: Option<Duration>) {
+        self.escape_timeout = timeout;
+    }
+
+    /// Sets whether sequences which can't be parsed are reported as `Event::Unknown` instead of
+    /// being dropped.
+    pub(crate) fn set_report_unknown_sequences(&mut self, enabled: bool) {
+        self.report_unknown_sequences = enabled;
+    }
+
+    /// Sets whether control sequences which can't be parsed are reported as
+    /// `InternalEvent::ControlSequence`, since they may be responses to a query in flight.
+    pub(crate) fn set_capture_responses(&mut self, enabled: bool) {
+        self.capture_responses = enabled;
+    }
+
+    /// Sets whether a query waits for responses, in which case an unterminated control string is
+    /// kept waiting for the rest of the response instead of being taken as keys.
+    pub(crate) fn set_awaiting_responses(&mut self, awaiting: bool) {
+        self.awaiting_responses = awaiting;
+    }
+
+    /// Sets the mouse encoding the terminal uses, which is needed to decode the UTF-8 and
+    /// SGR-Pixels encodings.
+    pub(crate) fn set_mouse_encoding(&mut self, encoding: MouseEncoding) {
+        self.mouse_encoding = encoding;
+    }
+
+    /// Sets how bracketed pastes are reported.
+    #[cfg(feature = "bracketed-paste")]
+    pub(crate) fn set_paste_options(&mut self, options: PasteOptions) {
+        self.paste_options = options;
+    }
+
+    /// Returns whether a resize to the given size `(columns, rows)` is a duplicate, because the
+    /// other source just reported the same size. Terminals with in-band resize notifications
+    /// still send `SIGWINCH`, and either may be read first.
+    pub(crate) fn is_duplicate_resize(&mut self, source: ResizeSource, size: (u16, u16)) -> bool {
+        match self.last_resize.take() {
+            Some((last_source, last_size)) if last_source != source && last_size == size => true,
+            _ => {
+                self.last_resize = Some((source, size));
+                false
+            }
+        }
+    }
+
+    /// Shortens the given poll timeout so that it doesn't outlast the escape timeout of the
+    /// incomplete sequence, if there's one.
+    pub(crate) fn limit_timeout(&self, timeout: Option<Duration>) -> Option<Duration> {
+        match self.escape_deadline {
+            Some(deadline) => {
+                let leftover = deadline.saturating_duration_since(Instant::now());
+                Some(timeout.map_or(leftover, |timeout| timeout.min(leftover)))
+            }
+            None => timeout,
+        }
+    }
+
+    /// Flushes the incomplete sequence if its escape timeout has elapsed.
+    ///
+    /// A lone `ESC` becomes the Esc key, `ESC [` and `ESC O` become the Alt modified character,
+    /// and an unterminated OSC, DCS or APC string becomes the Alt modified introducer followed by
+    /// the keys of its content. Anything else is discarded.
+    pub(crate) fn flush_expired(&mut self) {
+        if !matches!(self.escape_deadline, Some(deadline) if deadline <= Instant::now()) {
+            return;
+        }
+
+        match self.state {
+            _ if self.is_key_prefix() => self.flush_key_prefix(),
+            State::Ss3 | State::CsiEntry => {
+                let key_event =
+                    KeyEvent::new(KeyCode::Char(self.buffer[1] as char), KeyModifiers::ALT);
+                self.internal_events
+                    .push_back(InternalEvent::Event(Event::Key(key_event.normalize_case())));
+                self.reset();
+            }
+            // Keep waiting for the rest of a response, without a deadline until more input
+            // arrives.
+            State::ControlString | State::ControlStringEscape => self.escape_deadline = None,
+            _ => self.discard(),
+        }
+    }
+
+    // Whether the buffer holds the start of a sequence which may be keys as well: a lone `ESC`, or
+    // a control string unless it may be the response to a query.
+    fn is_key_prefix(&self) -> bool {
+        match self.state {
+            State::Escape => true,
+            State::ControlString | State::ControlStringEscape => !self.awaiting_responses,
+            _ => false,
+        }
+    }
+
+    // Reports the sequence the buffer starts, as checked by `is_key_prefix`, as keys.
+    fn flush_key_prefix(&mut self) {
+        match self.state {
+            State::ControlString => self.cancel_control_string(),
+            State::ControlStringEscape => {
+                self.cancel_control_string();
+                self.advance_byte(b'\x1B');
+                if self.state == State::Escape {
+                    self.dispatch();
+                }
+            }
+            _ => self.dispatch(),
+        }
+    }
+
+    // Reports the introducer of a control string which isn't one after all as an Alt modified
+    // key, and parses the content read so far again as input.
+    fn cancel_control_string(&mut self) {
+        let content = self.buffer.split_off(2);
+        self.dispatch();
+        for byte in content {
+            self.advance_byte(byte);
+        }
+    }
+
+    // Whether an incomplete sequence is waiting for more input.
+    fn is_pending(&self) -> bool {
+        match self.state {
+            State::Ground => false,
+            // A paste can't be confused with a key, so it's never flushed early.
+            #[cfg(feature = "bracketed-paste")]
+            State::Paste => false,
+            _ => true,
+        }
+    }
+
+    fn advance_byte(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => {
+                self.buffer.push(byte);
+                if byte == b'\x1B' {
+                    self.state = State::Escape;
+                } else {
+                    self.begin_char(byte);
+                }
+            }
+            State::Utf8(remaining) => {
+                self.buffer.push(byte);
+                if remaining > 1 && byte & 0b1100_0000 == 0b1000_0000 {
+                    self.state = State::Utf8(remaining - 1);
+                } else {
+                    // Either the character is complete or the byte is not a valid continuation
+                    // byte. `parse_event` reports the latter as an error.
+                    self.dispatch();
+                }
+            }
+            State::Escape => {
+                self.buffer.push(byte);
+                match byte {
+                    b'[' => self.state = State::CsiEntry,
+                    b'O' => self.state = State::Ss3,
+                    b']' | b'P' | b'_' => self.state = State::ControlString,
+                    // `ESC` followed by any other character is that character with the Alt modifier.
+                    _ => self.begin_char(byte),
+                }
+            }
+            State::Ss3 | State::CsiLinuxFunctionKey => {
+                self.buffer.push(byte);
+                self.dispatch();
+            }
+            State::CsiEntry => match byte {
+                b'M' => {
+                    self.buffer.push(byte);
+                    self.state = State::NormalMouse(3);
+                }
+                b'[' => {
+                    self.buffer.push(byte);
+                    self.state = State::CsiLinuxFunctionKey;
+                }
+                _ => self.advance_csi(byte),
+            },
+            State::CsiParam => self.advance_csi(byte),
+            State::NormalMouse(mut remaining) => {
+                self.buffer.push(byte);
+                // In the UTF-8 encoding, values from 96 on take two bytes.
+                if self.mouse_encoding == MouseEncoding::Utf8 && (0xC0..=0xDF).contains(&byte) {
+                    remaining += 1;
+                }
+                if remaining > 1 {
+                    self.state = State::NormalMouse(remaining - 1);
+                } else {
+                    self.dispatch();
+                }
+            }
+            State::ControlString => {
+                if byte == b'\x1B' {
+                    self.state = State::ControlStringEscape;
+                } else {
+                    self.buffer.push(byte);
+                    // BEL terminates the string.
+                    if byte == b'\x07' {
+                        self.dispatch();
+                    } else if self.buffer.len() > CONTROL_STRING_MAX_LEN && !self.awaiting_responses
+                    {
+                        // Report what was read so far if enabled and drop the rest.
+                        self.discard();
+                        self.state = State::OverlongControlString;
+                    }
+                }
+            }
+            State::ControlStringEscape => {
+                if byte == b'\\' {
+                    self.buffer.extend_from_slice(b"\x1B\\");
+                    self.dispatch();
+                } else {
+                    // Any other `ESC` sequence cancels the string, which was typed keys then.
+                    self.cancel_control_string();
+                    self.advance_byte(b'\x1B');
+                    self.advance_byte(byte);
+                }
+            }
+            State::OverlongControlString => match byte {
+                b'\x07' => self.reset(),
+                b'\x1B' => self.state = State::OverlongControlStringEscape,
+                _ => {}
+            },
+            State::OverlongControlStringEscape => {
+                // `ESC \` ends the dropped string, any other `ESC` sequence cancels it.
+                self.reset();
+                if byte != b'\\' {
+                    self.advance_byte(b'\x1B');
+                    self.advance_byte(byte);
+                }
+            }
+            #[cfg(feature = "bracketed-paste")]
+            State::Paste => {
+                if byte == PASTE_END[self.paste.end_matched] {
+                    self.paste.end_matched += 1;
+                    if self.paste.end_matched == PASTE_END.len() {
+                        self.end_paste();
+                    }
+                } else {
+                    // The bytes which looked like the end of the paste are content after all.
+                    let matched = std::mem::take(&mut self.paste.end_matched);
+                    self.push_paste_content(&PASTE_END[..matched]);
+                    if byte == PASTE_END[0] {
+                        self.paste.end_matched = 1;
+                    } else {
+                        self.push_paste_content(&[byte]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn begin_paste(&mut self) {
+        self.buffer.clear();
+        self.state = State::Paste;
+        if self.paste_options.streaming {
+            self.internal_events
+                .push_back(InternalEvent::Event(Event::PasteStart));
+        }
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn push_paste_content(&mut self, bytes: &[u8]) {
+        let room = match self.paste_options.max_size {
+            Some(max_size) => max_size.saturating_sub(self.paste.size),
+            None => usize::MAX,
+        };
+        let len = bytes.len().min(room);
+        self.paste.truncated |= len < bytes.len();
+        self.paste.size += len;
+        self.buffer.extend_from_slice(&bytes[..len]);
+    }
+
+    // Emits the paste content read so far as a chunk, except for an incomplete character at the
+    // end of text, unless the paste has ended.
+    #[cfg(feature = "bracketed-paste")]
+    fn flush_paste_chunk(&mut self, end: bool) {
+        let len = if self.paste_options.raw_bytes || end {
+            self.buffer.len()
+        } else {
+            complete_utf8_len(&self.buffer)
+        };
+        if len == 0 {
+            return;
+        }
+
+        let data = if self.paste_options.raw_bytes {
+            PasteData::Bytes(self.buffer[..len].to_vec())
+        } else {
+            PasteData::Text(String::from_utf8_lossy(&self.buffer[..len]).into_owned())
+        };
+        self.buffer.drain(..len);
+        self.internal_events
+            .push_back(InternalEvent::Event(Event::PasteChunk(data)));
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn end_paste(&mut self) {
+        let truncated = self.paste.truncated;
+        // Don't cut a character in two where the paste was truncated.
+        if truncated && !self.paste_options.raw_bytes {
+            self.buffer.truncate(complete_utf8_len(&self.buffer));
+        }
+
+        let event = if self.paste_options.streaming {
+            self.flush_paste_chunk(true);
+            Event::PasteEnd { truncated }
+        } else if self.paste_options.raw_bytes {
+            Event::PasteBytes(std::mem::replace(
+                &mut self.buffer,
+                Vec::with_capacity(BUFFER_CAPACITY),
+            ))
+        } else {
+            Event::Paste(String::from_utf8_lossy(&self.buffer).into_owned())
+        };
+        self.internal_events.push_back(InternalEvent::Event(event));
+        self.paste = PasteProgress::default();
+        self.reset();
+    }
+
+    // Starts reading a (possibly `ESC` prefixed) character whose first byte is `byte`.
+    fn begin_char(&mut self, byte: u8) {
+        let continuation_bytes = match byte {
+            // https://en.wikipedia.org/wiki/UTF-8#Description
+            0xC0..=0xDF => 1, // 110xxxxx 10xxxxxx
+            0xE0..=0xEF => 2, // 1110xxxx 10xxxxxx 10xxxxxx
+            0xF0..=0xF7 => 3, // 11110xxx 10xxxxxx 10xxxxxx 10xxxxxx
+            _ => 0,
+        };
+
+        if continuation_bytes == 0 {
+            self.dispatch();
+        } else {
+            self.state = State::Utf8(continuation_bytes);
+        }
+    }
+
+    fn advance_csi(&mut self, byte: u8) {
+        match byte {
+            // Parameter and intermediate bytes.
+            0x20..=0x3F => {
+                self.buffer.push(byte);
+                self.state = State::CsiParam;
+            }
+            // Final byte.
+            0x40..=0x7E => {
+                self.buffer.push(byte);
+
+                #[cfg(feature = "bracketed-paste")]
+                if self.buffer == b"\x1B[200~" {
+                    self.begin_paste();
+                    return;
+                }
+
+                self.dispatch();
+            }
+            // A control character cancels the sequence and is handled on its own, which also
+            // makes `ESC` start a new sequence.
+            0x00..=0x1F => {
+                self.discard();
+                self.advance_byte(byte);
+            }
+            _ => {
+                self.buffer.push(byte);
+                self.discard();
+            }
+        }
+    }
+
+    fn dispatch(&mut self) {
+        let result =
+            if self.mouse_encoding == MouseEncoding::Utf8 && self.buffer.starts_with(b"\x1B[M") {
+                parse_csi_utf8_mouse(&self.buffer)
+            } else {
+                parse_event(&self.buffer, false)
+            };
+
+        match result {
+            Ok(Some(mut ie)) => {
+                // SGR-Pixels mode uses the same sequences as SGR mode.
+                if let InternalEvent::Event(Event::Mouse(mouse_event)) = &mut ie {
+                    if self.mouse_encoding == MouseEncoding::SgrPixels
+                        && self.buffer.starts_with(b"\x1B[<")
+                    {
+                        mouse_event.position_unit = MousePositionUnit::Pixel;
+                    }
+                }
+                if let InternalEvent::Event(Event::Key(key_event)) = &mut ie {
+                    if self.buffer.starts_with(b"\x1B[") && self.buffer.ends_with(b"_") {
+                        self.track_win32_key(key_event);
+                    }
+                }
+                if let InternalEvent::Event(Event::WindowResize(size)) = ie {
+                    if self.is_duplicate_resize(ResizeSource::InBand, (size.columns, size.rows)) {
+                        self.reset();
+                        return;
+                    }
+                }
+                self.internal_events.push_back(ie);
+                self.reset();
+            }
+            // The sequence is complete but isn't one we know how to handle, or it can't be parsed
+            // (not enough parameters, parameter is not a number, ...).
+            Ok(None) | Err(_) => self.discard(),
+        }
+    }
+
+    // Turns a win32-input-mode press of the key which is already pressed into a repeat.
+    fn track_win32_key(&mut self, key_event: &mut KeyEvent) {
+        // Shift may be pressed or released while the key is held.
+        let is_pressed_key = match (self.win32_pressed_key, key_event.code) {
+            (Some(KeyCode::Char(pressed)), KeyCode::Char(c)) => {
+                pressed.to_lowercase().eq(c.to_lowercase())
+            }
+            (pressed, code) => pressed == Some(code),
+        };
+        match key_event.kind {
+            KeyEventKind::Press if is_pressed_key => key_event.kind = KeyEventKind::Repeat,
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                self.win32_pressed_key = Some(key_event.code)
+            }
+            KeyEventKind::Release if is_pressed_key => self.win32_pressed_key = None,
+            KeyEventKind::Release => {}
+        }
+    }
+
+    // Drops the current sequence and continues with the next one, reporting the dropped bytes
+    // if enabled.
+    fn discard(&mut self) {
+        if self.capture_responses {
+            if let Some(csi) = parse_csi_sequence(&self.buffer) {
+                self.internal_events
+                    .push_back(InternalEvent::ControlSequence(csi));
+                self.reset();
+                return;
+            }
+        }
+        if self.report_unknown_sequences && !self.buffer.is_empty() {
+            let sequence = UnknownSequence::new(&self.buffer);
+            self.internal_events
+                .push_back(InternalEvent::Event(Event::Unknown(sequence)));
+        }
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Ground;
+        self.escape_deadline = None;
+        self.buffer.clear();
+        self.buffer.shrink_to(BUFFER_CAPACITY);
+    }
+}
+
+// Returns the length of `bytes` without the incomplete UTF-8 character at its end, if any.
+#[cfg(feature = "bracketed-paste")]
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    for i in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - i];
+        // Look for the first byte of the last character.
+        if byte & 0b1100_0000 != 0b1000_0000 {
+            let width = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if width > i {
+                bytes.len() - i
+            } else {
+                bytes.len()
+            };
+        }
+    }
+    bytes.len()
+}
+
+impl Iterator for Parser {
+    type Item = InternalEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.internal_events.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{
+        CsiSequence, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    };
+
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<InternalEvent> {
+        let mut parser = Parser::default();
+        for chunk in chunks {
+            parser.advance(chunk, false);
+        }
+        parser.collect()
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> InternalEvent {
+        InternalEvent::Event(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    #[test]
+    fn test_sequences_in_one_read() {
+        assert_eq!(
+            parse(&[b"a\x1B[D\x1BOP\x1B[3~\x1Bc\r"]),
+            vec![
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Left, KeyModifiers::NONE),
+                key(KeyCode::F(1), KeyModifiers::NONE),
+                key(KeyCode::Delete, KeyModifiers::NONE),
+                key(KeyCode::Char('c'), KeyModifiers::ALT),
+                key(KeyCode::Enter, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequences_split_across_reads() {
+        let mut parser = Parser::default();
+        parser.advance(b"\x1B", true);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"[1;", false);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"5A\xC3", false);
+        assert_eq!(parser.next(), Some(key(KeyCode::Up, KeyModifiers::CONTROL)));
+        assert_eq!(parser.next(), None);
+        parser.advance(b"\xB1", false);
+        assert_eq!(
+            parser.next(),
+            Some(key(KeyCode::Char('ñ'), KeyModifiers::NONE))
+        );
+    }
+
+    #[test]
+    fn test_sequences_byte_by_byte() {
+        let input = b"\x1B[;1:3B\x1B[<0;20;10m\x1B[M0\x60\x70\x1B[[A\x1B\xC3\xB1";
+        let mut parser = Parser::default();
+        for byte in input {
+            parser.advance(&[*byte], true);
+        }
+        assert_eq!(parser.collect::<Vec<_>>(), parse(&[input]));
+
+        let events = parse(&[input]);
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[0],
+            InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Down,
+                KeyModifiers::empty(),
+                KeyEventKind::Release,
+            )))
+        );
+        assert_eq!(events[4], key(KeyCode::Char('ñ'), KeyModifiers::ALT));
+    }
+
+    #[test]
+    fn test_esc_at_end_of_input() {
+        assert_eq!(
+            parse(&[b"\x1B"]),
+            vec![key(KeyCode::Esc, KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            parse(&[b"\x1B\x1B"]),
+            vec![key(KeyCode::Esc, KeyModifiers::NONE)]
+        );
+
+        let mut parser = Parser::default();
+        parser.advance(b"\x1B", true);
+        assert_eq!(parser.next(), None);
+    }
+
+    #[test]
+    fn test_escape_timeout_flushes_incomplete_sequence() {
+        let mut parser = Parser::default();
+        parser.set_escape_timeout(Some(Duration::from_millis(10)));
+
+        parser.advance(b"\x1B", false);
+        parser.flush_expired();
+        assert_eq!(parser.next(), None);
+        assert!(parser.limit_timeout(None).unwrap() <= Duration::from_millis(10));
+        assert!(parser.limit_timeout(Some(Duration::ZERO)).unwrap() == Duration::ZERO);
+
+        std::thread::sleep(Duration::from_millis(20));
+        parser.flush_expired();
+        assert_eq!(parser.next(), Some(key(KeyCode::Esc, KeyModifiers::NONE)));
+        assert_eq!(parser.limit_timeout(None), None);
+
+        parser.advance(b"\x1B[", false);
+        std::thread::sleep(Duration::from_millis(20));
+        parser.flush_expired();
+        assert_eq!(
+            parser.next(),
+            Some(key(KeyCode::Char('['), KeyModifiers::ALT))
+        );
+    }
+
+    #[test]
+    fn test_escape_timeout_joins_split_sequence() {
+        let mut parser = Parser::default();
+        parser.set_escape_timeout(Some(Duration::from_secs(60)));
+
+        parser.advance(b"\x1B", false);
+        parser.flush_expired();
+        assert_eq!(parser.next(), None);
+        parser.advance(b"[A", false);
+        assert_eq!(parser.next(), Some(key(KeyCode::Up, KeyModifiers::NONE)));
+        assert_eq!(parser.limit_timeout(None), None);
+    }
+
+    #[test]
+    fn test_invalid_sequences_are_dropped() {
+        assert_eq!(
+            parse(&[b"\x1B[X", b"\xC3\x28", b"b"]),
+            vec![key(KeyCode::Char('b'), KeyModifiers::NONE)]
+        );
+    }
+
+    #[test]
+    fn test_control_strings() {
+        let mut parser = Parser::default();
+        parser.set_awaiting_responses(true);
+        parser.advance(b"\x1B]11;rgb:ffff/", false);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"ffff/ffff\x1B", false);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"\\\x1BP1$r0m\x07a", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![
+                InternalEvent::OperatingSystemCommand(b"11;rgb:ffff/ffff/ffff".to_vec()),
+                InternalEvent::DeviceControlString(b"1$r0m".to_vec()),
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+            ]
+        );
+
+        // Just the introducer is an Alt modified key.
+        assert_eq!(
+            parse(&[b"\x1B]", b"\x1B_"]),
+            vec![
+                key(KeyCode::Char(']'), KeyModifiers::ALT),
+                key(KeyCode::Char('_'), KeyModifiers::ALT),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancelled_control_strings_are_keys() {
+        let alt_p = key(KeyCode::Char('P'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+
+        // `ESC` followed by anything but `\` cancels the string.
+        assert_eq!(
+            parse(&[b"\x1B_i=1\x1B[A"]),
+            vec![
+                key(KeyCode::Char('_'), KeyModifiers::ALT),
+                key(KeyCode::Char('i'), KeyModifiers::NONE),
+                key(KeyCode::Char('='), KeyModifiers::NONE),
+                key(KeyCode::Char('1'), KeyModifiers::NONE),
+                key(KeyCode::Up, KeyModifiers::NONE),
+            ]
+        );
+        assert_eq!(parse(&[b"\x1BP\x1BP"]), vec![alt_p.clone(), alt_p.clone()]);
+        // An unterminated string at the end of the input is taken as keys.
+        assert_eq!(
+            parse(&[b"\x1BPab"]),
+            vec![
+                alt_p.clone(),
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Char('b'), KeyModifiers::NONE),
+            ]
+        );
+        assert_eq!(
+            parse(&[b"\x1BPa\x1B"]),
+            vec![
+                alt_p.clone(),
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Esc, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlong_control_strings_are_dropped() {
+        let a = key(KeyCode::Char('a'), KeyModifiers::NONE);
+        let mut too_long = b"\x1B]".to_vec();
+        too_long.extend_from_slice(&[b'a'; CONTROL_STRING_MAX_LEN + 1]);
+
+        // The rest of a string which is too long is dropped up to its terminator, instead of
+        // being taken as keys.
+        let mut parser = Parser::default();
+        parser.advance(&too_long, true);
+        parser.advance(b"aaa\x1B\\a", false);
+        assert_eq!(parser.collect::<Vec<_>>(), vec![a.clone()]);
+
+        let mut parser = Parser::default();
+        parser.advance(&too_long, true);
+        parser.advance(b"aaa\x07a", false);
+        assert_eq!(parser.collect::<Vec<_>>(), vec![a.clone()]);
+
+        // Any other `ESC` sequence cancels it.
+        let mut parser = Parser::default();
+        parser.advance(&too_long, true);
+        parser.advance(b"aaa\x1B[Aa", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![key(KeyCode::Up, KeyModifiers::NONE), a.clone()]
+        );
+
+        // What was read before is reported if enabled.
+        let mut parser = Parser::default();
+        parser.set_report_unknown_sequences(true);
+        parser.advance(&too_long, true);
+        parser.advance(b"aaa\x07", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![InternalEvent::Event(Event::Unknown(UnknownSequence::new(
+                &too_long[..CONTROL_STRING_MAX_LEN + 1]
+            )))]
+        );
+
+        // A response to a query isn't limited.
+        let mut parser = Parser::default();
+        parser.set_awaiting_responses(true);
+        parser.advance(&too_long, true);
+        parser.advance(b"\x07", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![InternalEvent::OperatingSystemCommand(
+                too_long[2..].to_vec()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_control_strings_wait_for_responses() {
+        let mut parser = Parser::default();
+        parser.set_awaiting_responses(true);
+        parser.advance(b"\x1B]11;rgb:0/", false);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"0/0\x1B\\", false);
+        assert_eq!(
+            parser.next(),
+            Some(InternalEvent::OperatingSystemCommand(
+                b"11;rgb:0/0/0".to_vec()
+            ))
+        );
+
+        // The escape timeout doesn't flush a response either.
+        parser.set_escape_timeout(Some(Duration::ZERO));
+        parser.advance(b"\x1B]11;rgb:0/", false);
+        parser.flush_expired();
+        assert_eq!(parser.next(), None);
+        assert_eq!(parser.limit_timeout(None), None);
+
+        parser.set_awaiting_responses(false);
+        parser.advance(b"0", false);
+        parser.flush_expired();
+        assert_eq!(
+            parser.next(),
+            Some(key(KeyCode::Char(']'), KeyModifiers::ALT))
+        );
+        assert_eq!(
+            parser
+                .map(|event| match event {
+                    InternalEvent::Event(Event::Key(KeyEvent {
+                        code: KeyCode::Char(c),
+                        ..
+                    })) => c,
+                    event => panic!("unexpected event {:?}", event),
+                })
+                .collect::<String>(),
+            "11;rgb:0/0"
+        );
+    }
+
+    #[test]
+    fn test_mouse_sgr_pixels_encoding() {
+        let mouse_event = |position_unit| {
+            InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 319,
+                row: 99,
+                modifiers: KeyModifiers::NONE,
+                position_unit,
+            }))
+        };
+
+        let mut parser = Parser::default();
+        parser.advance(b"\x1B[<0;320;100M", false);
+        assert_eq!(parser.next(), Some(mouse_event(MousePositionUnit::Cell)));
+
+        parser.set_mouse_encoding(MouseEncoding::SgrPixels);
+        parser.advance(b"\x1B[<0;320;100M", false);
+        assert_eq!(parser.next(), Some(mouse_event(MousePositionUnit::Pixel)));
+    }
+
+    #[test]
+    fn test_mouse_utf8_encoding() {
+        // Column 320 and row 100 are encoded as U+0160 and U+0084, split across reads.
+        let input = "\x1B[M \u{160}\u{84}".as_bytes();
+        let mut parser = Parser::default();
+        parser.set_mouse_encoding(MouseEncoding::Utf8);
+        parser.advance(&input[..5], false);
+        assert_eq!(parser.next(), None);
+        parser.advance(&input[5..], false);
+        assert_eq!(
+            parser.next(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 319,
+                row: 99,
+                modifiers: KeyModifiers::NONE,
+                position_unit: MousePositionUnit::Cell,
+            })))
+        );
+        assert_eq!(parser.next(), None);
+    }
+
+    #[test]
+    fn test_report_unknown_sequences() {
+        let mut parser = Parser::default();
+        parser.set_report_unknown_sequences(true);
+        parser.advance(b"\x1B[>1;2:3 X\xC3\x28\x1B[1\x03", false);
+
+        let unknown =
+            |bytes: &[u8]| InternalEvent::Event(Event::Unknown(UnknownSequence::new(bytes)));
+        let events = parser.collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                unknown(b"\x1B[>1;2:3 X"),
+                unknown(b"\xC3\x28"),
+                unknown(b"\x1B[1"),
+                key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            ]
+        );
+        match &events[0] {
+            InternalEvent::Event(Event::Unknown(sequence)) => assert_eq!(
+                sequence.csi(),
+                Some(CsiSequence {
+                    private_marker: Some(b'>'),
+                    parameters: vec![vec![1], vec![2, 3]],
+                    intermediates: b" ".to_vec(),
+                    final_byte: b'X',
+                })
+            ),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        // Only the start of a long sequence is kept.
+        let sequence = UnknownSequence::new(format!("\x1B[{}X", "1;".repeat(20)).as_bytes());
+        assert!(sequence.is_truncated());
+        assert_eq!(sequence.bytes().len(), UnknownSequence::CAPACITY);
+        assert_eq!(sequence.csi(), None);
+    }
+
+    #[test]
+    fn test_control_character_cancels_csi() {
+        assert_eq!(
+            parse(&[b"\x1B[1;\x03\x1B[A"]),
+            vec![
+                key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                key(KeyCode::Up, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_many_keys_throughput() {
+        const COUNT: usize = 1024 * 1024;
+
+        let input = "aé".repeat(COUNT / 2);
+        let mut parser = Parser::default();
+        for chunk in input.as_bytes().chunks(1024) {
+            parser.advance(chunk, false);
+        }
+        assert_eq!(parser.count(), COUNT);
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_bracketed_paste_split_across_reads() {
+        assert_eq!(
+            parse(&[b"\x1B[20", b"0~on and", b" on\x1B[2", b"01~a"]),
+            vec![
+                InternalEvent::Event(Event::Paste("on and on".to_string())),
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_multi_megabyte_bracketed_paste_throughput() {
+        const SIZE: usize = 8 * 1024 * 1024;
+
+        let content = "lorem ipsum\x1B[A\n".repeat(SIZE / 16);
+        let mut input = b"\x1B[200~".to_vec();
+        input.extend_from_slice(content.as_bytes());
+        input.extend_from_slice(b"\x1B[201~");
+
+        let mut parser = Parser::default();
+        for chunk in input.chunks(1024) {
+            parser.advance(chunk, false);
+        }
+        assert_eq!(
+            parser.next(),
+            Some(InternalEvent::Event(Event::Paste(content)))
+        );
+        assert_eq!(parser.next(), None);
+        assert!(parser.buffer.capacity() <= BUFFER_CAPACITY);
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn paste_parser(options: PasteOptions) -> Parser {
+        let mut parser = Parser::default();
+        parser.set_paste_options(options);
+        parser
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn paste_event(event: Event) -> InternalEvent {
+        InternalEvent::Event(event)
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_bracketed_paste_end_lookalikes() {
+        assert_eq!(
+            parse(&[b"\x1B[200~a\x1B[201x\x1B\x1B[2", b"01~"]),
+            vec![paste_event(Event::Paste("a\x1B[201x\x1B".to_string()))]
+        );
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_streaming_bracketed_paste() {
+        let mut parser = paste_parser(PasteOptions {
+            streaming: true,
+            ..PasteOptions::default()
+        });
+        let text = |text: &str| paste_event(Event::PasteChunk(PasteData::Text(text.to_string())));
+
+        // "é" is split across reads, as is the end of the paste.
+        parser.advance(b"\x1B[200~ab\xC3", false);
+        assert_eq!(
+            parser.by_ref().collect::<Vec<_>>(),
+            vec![paste_event(Event::PasteStart), text("ab")]
+        );
+        parser.advance(b"\xA9c\x1B[20", false);
+        assert_eq!(parser.by_ref().collect::<Vec<_>>(), vec![text("\u{e9}c")]);
+        parser.advance(b"1~d", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![
+                paste_event(Event::PasteEnd { truncated: false }),
+                key(KeyCode::Char('d'), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_raw_bracketed_paste() {
+        let mut parser = paste_parser(PasteOptions {
+            raw_bytes: true,
+            ..PasteOptions::default()
+        });
+        parser.advance(b"\x1B[200~\xFF\x00\xC3\x1B[201~", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![paste_event(Event::PasteBytes(b"\xFF\x00\xC3".to_vec()))]
+        );
+
+        let mut parser = paste_parser(PasteOptions {
+            streaming: true,
+            raw_bytes: true,
+            ..PasteOptions::default()
+        });
+        parser.advance(b"\x1B[200~\xFF\xC3", false);
+        parser.advance(b"\xA9\x1B[201~", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![
+                paste_event(Event::PasteStart),
+                paste_event(Event::PasteChunk(PasteData::Bytes(b"\xFF\xC3".to_vec()))),
+                paste_event(Event::PasteChunk(PasteData::Bytes(b"\xA9".to_vec()))),
+                paste_event(Event::PasteEnd { truncated: false }),
+            ]
+        );
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_bracketed_paste_max_size() {
+        let mut parser = paste_parser(PasteOptions {
+            max_size: Some(4),
+            ..PasteOptions::default()
+        });
+        // The truncated "é" is dropped.
+        parser.advance(b"\x1B[200~abc\xC3\xA9def\x1B[201~", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![paste_event(Event::Paste("abc".to_string()))]
+        );
+
+        let mut parser = paste_parser(PasteOptions {
+            streaming: true,
+            max_size: Some(4),
+            ..PasteOptions::default()
+        });
+        parser.advance(b"\x1B[200~ab", false);
+        parser.advance(b"cdef\x1B[201~", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![
+                paste_event(Event::PasteStart),
+                paste_event(Event::PasteChunk(PasteData::Text("ab".to_string()))),
+                paste_event(Event::PasteChunk(PasteData::Text("cd".to_string()))),
+                paste_event(Event::PasteEnd { truncated: true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_win32_input_mode_repeat() {
+        let kinds = parse(&[
+            b"\x1B[65;30;97;1;0;1_\x1B[65;30;97;1;0;1_\x1B[16;42;0;1;16;1_",
+            b"\x1B[65;30;65;1;16;1_\x1B[16;42;0;0;0;1_\x1B[65;30;97;0;0;1_\x1B[65;30;97;1;0;1_",
+        ])
+        .into_iter()
+        .map(|event| match event {
+            InternalEvent::Event(Event::Key(key_event)) => key_event.kind,
+            event => panic!("unexpected event {:?}", event),
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                KeyEventKind::Press,
+                KeyEventKind::Repeat,
+                // Pressing Shift while 'a' is held stops the repeat.
+                KeyEventKind::Press,
+                KeyEventKind::Press,
+                KeyEventKind::Release,
+                KeyEventKind::Release,
+                KeyEventKind::Press,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_resizes() {
+        let mut parser = Parser::default();
+        // The in-band notification is read first.
+        parser.advance(b"\x1B[48;24;80;480;800t", false);
+        assert_eq!(
+            parser.next(),
+            Some(InternalEvent::Event(Event::WindowResize(
+                crate::terminal::WindowSize {
+                    rows: 24,
+                    columns: 80,
+                    width: 800,
+                    height: 480,
+                }
+            )))
+        );
+        assert!(parser.is_duplicate_resize(ResizeSource::Signal, (80, 24)));
+        // The signal is read first.
+        assert!(!parser.is_duplicate_resize(ResizeSource::Signal, (100, 30)));
+        parser.advance(b"\x1B[48;30;100;600;1000t", false);
+        assert_eq!(parser.next(), None);
+        // Only the other source's resize is a duplicate, once.
+        assert!(!parser.is_duplicate_resize(ResizeSource::Signal, (120, 40)));
+        assert!(!parser.is_duplicate_resize(ResizeSource::Signal, (120, 40)));
+        assert!(!parser.is_duplicate_resize(ResizeSource::InBand, (80, 24)));
+        assert!(parser.is_duplicate_resize(ResizeSource::Signal, (80, 24)));
+        assert!(!parser.is_duplicate_resize(ResizeSource::Signal, (80, 24)));
+    }
+
+    #[test]
+    fn test_capture_responses() {
+        let mut parser = Parser::default();
+        parser.advance(b"\x1B[?2004;1$y", false);
+        assert!(parser.next().is_none());
+
+        parser.set_capture_responses(true);
+        parser.advance(b"\x1B[?2004;1$y", false);
+        match parser.next() {
+            Some(InternalEvent::ControlSequence(csi)) => {
+                assert_eq!(csi.private_marker, Some(b'?'));
+                assert_eq!(csi.parameters, vec![vec![2004], vec![1]]);
+                assert_eq!(csi.intermediates, b"$");
+                assert_eq!(csi.final_byte, b'y');
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        // Sequences crossterm parses itself aren't captured.
+        parser.advance(b"\x1B[5;10R", false);
+        assert!(matches!(
+            parser.next(),
+            Some(InternalEvent::CursorPosition(9, 4))
+        ));
+    }
+}
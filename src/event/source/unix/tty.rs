@@ -2,20 +2,25 @@
 use std::os::unix::prelude::AsRawFd;
 use std::{collections::VecDeque, io, os::unix::net::UnixStream, time::Duration};
 
+#[cfg(all(not(feature = "libc"), feature = "event-stream"))]
+use rustix::fd::AsFd;
 #[cfg(not(feature = "libc"))]
-use rustix::fd::{AsFd, AsRawFd};
+use rustix::fd::AsRawFd;
 
-use signal_hook::low_level::pipe;
+use signal_hook::iterator::{backend::SignalDelivery, exfiltrator::SignalOnly};
 
 use crate::event::timeout::PollTimeout;
-use crate::event::Event;
 use filedescriptor::{poll, pollfd, POLLIN};
 
 #[cfg(feature = "event-stream")]
 use crate::event::sys::Waker;
-use crate::event::{source::EventSource, sys::unix::parse::parse_event, InternalEvent};
+use crate::event::{source::EventSource, InternalEvent};
 use crate::terminal::sys::file_descriptor::{tty_fd, FileDesc};
 
+#[cfg(feature = "event-stream")]
+use super::nonblocking_unix_pair;
+use super::{advance_parser, parser::Parser, signal_delivery, signal_event};
+
 /// Holds a prototypical Waker and a receiver we can wait on when doing select().
 #[cfg(feature = "event-stream")]
 struct WakePipe {
@@ -43,18 +48,12 @@ pub(crate) struct UnixInternalEventSource {
     parser: Parser,
     tty_buffer: [u8; TTY_BUFFER_SIZE],
     tty: FileDesc<'static>,
-    winch_signal_receiver: UnixStream,
+    signals: SignalDelivery<UnixStream, SignalOnly>,
+    signal_events: VecDeque<InternalEvent>,
     #[cfg(feature = "event-stream")]
     wake_pipe: WakePipe,
 }
 
-fn nonblocking_unix_pair() -> io::Result<(UnixStream, UnixStream)> {
-    let (receiver, sender) = UnixStream::pair()?;
-    receiver.set_nonblocking(true)?;
-    sender.set_nonblocking(true)?;
-    Ok((receiver, sender))
-}
-
 impl UnixInternalEventSource {
     pub fn new() -> io::Result<Self> {
         UnixInternalEventSource::from_file_descriptor(tty_fd()?)
@@ -65,15 +64,8 @@ impl UnixInternalEventSource {
             parser: Parser::default(),
             tty_buffer: [0u8; TTY_BUFFER_SIZE],
             tty: input_fd,
-            winch_signal_receiver: {
-                let (receiver, sender) = nonblocking_unix_pair()?;
-                // Unregistering is unnecessary because EventSource is a singleton
-                #[cfg(feature = "libc")]
-                pipe::register(libc::SIGWINCH, sender)?;
-                #[cfg(not(feature = "libc"))]
-                pipe::register(rustix::process::Signal::Winch as i32, sender)?;
-                receiver
-            },
+            signals: signal_delivery()?,
+            signal_events: VecDeque::new(),
             #[cfg(feature = "event-stream")]
             wake_pipe: WakePipe::new()?,
         })
@@ -111,15 +103,12 @@ impl EventSource for UnixInternalEventSource {
         }
 
         #[cfg(not(feature = "event-stream"))]
-        let mut fds = [
-            make_pollfd(&self.tty),
-            make_pollfd(&self.winch_signal_receiver),
-        ];
+        let mut fds = [make_pollfd(&self.tty), make_pollfd(self.signals.get_read())];
 
         #[cfg(feature = "event-stream")]
         let mut fds = [
             make_pollfd(&self.tty),
-            make_pollfd(&self.winch_signal_receiver),
+            make_pollfd(self.signals.get_read()),
             make_pollfd(&self.wake_pipe.receiver),
         ];
 
@@ -128,7 +117,10 @@ impl EventSource for UnixInternalEventSource {
             if let Some(event) = self.parser.next() {
                 return Ok(Some(event));
             }
-            match poll(&mut fds, timeout.leftover()) {
+            if let Some(event) = self.signal_events.pop_front() {
+                return Ok(Some(event));
+            }
+            match poll(&mut fds, self.parser.limit_timeout(timeout.leftover())) {
                 Err(filedescriptor::Error::Poll(e)) | Err(filedescriptor::Error::Io(e)) => {
                     match e.kind() {
                         // retry on EINTR
@@ -144,11 +136,17 @@ impl EventSource for UnixInternalEventSource {
                 }
                 Ok(_) => (),
             };
+            // Flush an incomplete sequence if its escape timeout elapsed while polling.
+            self.parser.flush_expired();
+            if let Some(event) = self.parser.next() {
+                return Ok(Some(event));
+            }
             if fds[0].revents & POLLIN != 0 {
                 loop {
                     let read_count = read_complete(&self.tty, &mut self.tty_buffer)?;
                     if read_count > 0 {
-                        self.parser.advance(
+                        advance_parser(
+                            &mut self.parser,
                             &self.tty_buffer[..read_count],
                             read_count == TTY_BUFFER_SIZE,
                         );
@@ -158,29 +156,23 @@ impl EventSource for UnixInternalEventSource {
                         return Ok(Some(event));
                     }
 
-                    if read_count == 0 {
+                    // All available input was read. Don't block on reading the rest of an
+                    // incomplete sequence, polling again respects the escape timeout.
+                    if read_count < TTY_BUFFER_SIZE {
                         break;
                     }
                 }
             }
             if fds[1].revents & POLLIN != 0 {
-                #[cfg(feature = "libc")]
-                let fd = FileDesc::new(self.winch_signal_receiver.as_raw_fd(), false);
-                #[cfg(not(feature = "libc"))]
-                let fd = FileDesc::Borrowed(self.winch_signal_receiver.as_fd());
-                // drain the pipe
-                while read_complete(&fd, &mut [0; 1024])? != 0 {}
-                // TODO Should we remove tput?
-                //
-                // This can take a really long time, because terminal::size can
-                // launch new process (tput) and then it parses its output. It's
-                // not a really long time from the absolute time point of view, but
-                // it's a really long time from the mio, async-std/tokio executor, ...
-                // point of view.
-                let new_size = crate::terminal::size()?;
-                return Ok(Some(InternalEvent::Event(Event::Resize(
-                    new_size.0, new_size.1,
-                ))));
+                // `pending` drains the pipe
+                for signal in self.signals.pending() {
+                    if let Some(event) = signal_event(signal, &mut self.parser)? {
+                        self.signal_events.push_back(event);
+                    }
+                }
+                if let Some(event) = self.signal_events.pop_front() {
+                    return Ok(Some(event));
+                }
             }
 
             #[cfg(feature = "event-stream")]
@@ -206,73 +198,3 @@ impl EventSource for UnixInternalEventSource {
         self.wake_pipe.waker.clone()
     }
 }
-
-//
-// Following `Parser` structure exists for two reasons:
-//
-//  * mimic anes Parser interface
-//  * move the advancing, parsing, ... stuff out of the `try_read` method
-//
-#[derive(Debug)]
-struct Parser {
-    buffer: Vec<u8>,
-    internal_events: VecDeque<InternalEvent>,
-}
-
-impl Default for Parser {
-    fn default() -> Self {
-        Parser {
-            // This buffer is used for -> 1 <- ANSI escape sequence. Are we
-            // aware of any ANSI escape sequence that is bigger? Can we make
-            // it smaller?
-            //
-            // Probably not worth spending more time on this as "there's a plan"
-            // to use the anes crate parser.
-            buffer: Vec::with_capacity(256),
-            // TTY_BUFFER_SIZE is 1_024 bytes. How many ANSI escape sequences can
-            // fit? What is an average sequence length? Let's guess here
-            // and say that the average ANSI escape sequence length is 8 bytes. Thus
-            // the buffer size should be 1024/8=128 to avoid additional allocations
-            // when processing large amounts of data.
-            //
-            // There's no need to make it bigger, because when you look at the `try_read`
-            // method implementation, all events are consumed before the next TTY_BUFFER
-            // is processed -> events pushed.
-            internal_events: VecDeque::with_capacity(128),
-        }
-    }
-}
-
-impl Parser {
-    fn advance(&mut self, buffer: &[u8], more: bool) {
-        for (idx, byte) in buffer.iter().enumerate() {
-            let more = idx + 1 < buffer.len() || more;
-
-            self.buffer.push(*byte);
-
-            match parse_event(&self.buffer, more) {
-                Ok(Some(ie)) => {
-                    self.internal_events.push_back(ie);
-                    self.buffer.clear();
-                }
-                Ok(None) => {
-                    // Event can't be parsed, because we don't have enough bytes for
-                    // the current sequence. Keep the buffer and process next bytes.
-                }
-                Err(_) => {
-                    // Event can't be parsed (not enough parameters, parameter is not a number, ...).
-                    // Clear the buffer and continue with another sequence.
-                    self.buffer.clear();
-                }
-            }
-        }
-    }
-}
-
-impl Iterator for Parser {
-    type Item = InternalEvent;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.internal_events.pop_front()
-    }
-}
@@ -1,4 +1,5 @@
 use std::{
+    future::Future,
     io,
     pin::Pin,
     sync::{
@@ -12,6 +13,7 @@ use std::{
 };
 
 use futures_core::stream::Stream;
+use parking_lot::Mutex;
 
 use crate::event::{
     filter::EventFilter, lock_internal_event_reader, poll_internal, read_internal, sys::Waker,
@@ -144,3 +146,79 @@ impl Drop for EventStream {
         let _ = self.poll_internal_waker.wake();
     }
 }
+
+/// A future resolving to the result of a terminal query, see [`query_async`].
+#[derive(Debug)]
+pub struct QueryFuture<T> {
+    state: Arc<Mutex<QueryState<T>>>,
+}
+
+#[derive(Debug)]
+struct QueryState<T> {
+    result: Option<io::Result<T>>,
+    waker: Option<std::task::Waker>,
+}
+
+/// Runs a terminal query, such as [`cursor::position`](crate::cursor::position) or
+/// [`terminal::terminal_features`](crate::terminal::terminal_features), without blocking the
+/// executor.
+///
+/// **This function is not available by default. You have to use the `event-stream` feature flag
+/// to make it available.**
+///
+/// The query runs on its own thread and may be issued while an [`EventStream`] is alive. The
+/// responses of the terminal are passed to the query, the stream doesn't report them, and the
+/// stream keeps reporting the other events while the query waits for its responses.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::{
+///     cursor,
+///     event::{query_async, EventStream},
+/// };
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let mut reader = EventStream::new();
+/// // `reader` can be polled while the query waits for the response.
+/// let (column, row) = query_async(cursor::position).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn query_async<T, F>(query: F) -> QueryFuture<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(QueryState {
+        result: None,
+        waker: None,
+    }));
+
+    let query_state = state.clone();
+    thread::spawn(move || {
+        let result = query();
+        let mut state = query_state.lock();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+
+    QueryFuture { state }
+}
+
+impl<T> Future for QueryFuture<T> {
+    type Output = io::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
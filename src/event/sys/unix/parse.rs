@@ -1,10 +1,13 @@
 use std::io;
 
 use crate::event::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, KeyboardEnhancementFlags,
-    MediaKeyCode, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind, SynchronizedOutputMode,
-    ThemeMode,
+    AlternateKeys, CsiSequence, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState,
+    KeyModifiers, KeyText, KeyboardEnhancementFlags, MediaKeyCode, ModifierKeyCode, MouseButton,
+    MouseEvent, MouseEventKind, MousePositionUnit, SecondaryDeviceAttributes,
+    SynchronizedOutputMode, ThemeMode,
 };
+use crate::style::Color;
+use crate::terminal::WindowSize;
 
 use super::super::super::InternalEvent;
 
@@ -70,11 +73,23 @@ pub(crate) fn parse_event(
                                 val @ b'P'..=b'S' => Ok(Some(InternalEvent::Event(Event::Key(
                                     KeyCode::F(1 + val - b'P').into(),
                                 )))),
-                                _ => Err(could_not_parse_event_error()),
+                                // The keypad in application keypad mode
+                                val => match parse_ss3_keypad_key_code(val) {
+                                    Some(code) => Ok(Some(InternalEvent::Event(Event::Key(
+                                        KeyEvent::new_with_kind_and_state(
+                                            code,
+                                            KeyModifiers::empty(),
+                                            KeyEventKind::Press,
+                                            KeyEventState::KEYPAD,
+                                        ),
+                                    )))),
+                                    None => Err(could_not_parse_event_error()),
+                                },
                             }
                         }
                     }
                     b'[' => parse_csi(buffer),
+                    b']' | b'P' | b'_' if buffer.len() > 2 => parse_control_string(buffer),
                     b'\x1B' => Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Esc.into())))),
                     _ => parse_event(&buffer[1..], input_available).map(|event_option| {
                         event_option.map(|event| {
@@ -170,6 +185,8 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
         b'<' => return parse_csi_sgr_mouse(buffer),
         b'I' => Some(Event::FocusGained),
         b'O' => Some(Event::FocusLost),
+        // The virtual key code of a win32-input-mode record may be omitted.
+        b';' if buffer.ends_with(b"_") => return parse_csi_win32_input_mode(buffer),
         b';' => return parse_csi_modifier_key_code(buffer),
         // P, Q, and S for compatibility with Kitty keyboard protocol,
         // as the 1 in 'CSI 1 P' etc. must be omitted if there are no
@@ -185,6 +202,10 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
             b'y' => return parse_csi_synchronized_output_mode(buffer),
             _ => None,
         },
+        b'>' => match buffer[buffer.len() - 1] {
+            b'c' => return parse_csi_secondary_device_attributes(buffer),
+            _ => None,
+        },
         b'0'..=b'9' => {
             // Numbered escape code.
             if buffer.len() == 3 {
@@ -205,6 +226,8 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
                         b'~' => return parse_csi_special_key_code(buffer),
                         b'u' => return parse_csi_u_encoded_key_code(buffer),
                         b'R' => return parse_csi_cursor_position(buffer),
+                        b'_' => return parse_csi_win32_input_mode(buffer),
+                        b't' => return parse_csi_window_report(buffer),
                         _ => return parse_csi_modifier_key_code(buffer),
                     }
                 }
@@ -216,6 +239,151 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
     Ok(input_event.map(InternalEvent::Event))
 }
 
+/// Parses an OSC (`ESC ]`), DCS (`ESC P`) or APC (`ESC _`) string terminated by BEL or ST
+/// (`ESC \`).
+pub(crate) fn parse_control_string(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.len() > 2 && buffer[0] == b'\x1B');
+
+    let data = match buffer[2..]
+        .strip_suffix(b"\x07")
+        .or_else(|| buffer[2..].strip_suffix(b"\x1B\\"))
+    {
+        Some(data) => data.to_vec(),
+        None => return Ok(None),
+    };
+
+    let event = match buffer[1] {
+        b']' => InternalEvent::OperatingSystemCommand(data),
+        b'P' => InternalEvent::DeviceControlString(data),
+        b'_' => InternalEvent::ApplicationProgramCommand(data),
+        _ => return Err(could_not_parse_event_error()),
+    };
+
+    Ok(Some(event))
+}
+
+/// Splits a complete control sequence into its parts, or returns `None` if `buffer` isn't a
+/// well-formed control sequence.
+pub(crate) fn parse_csi_sequence(buffer: &[u8]) -> Option<CsiSequence> {
+    // ESC [ [private marker] parameters intermediates final
+    let (&final_byte, body) = buffer.strip_prefix(b"\x1B[")?.split_last()?;
+    if !(0x40..=0x7E).contains(&final_byte) {
+        return None;
+    }
+
+    let (private_marker, body) = match body.split_first() {
+        Some((&marker @ b'<'..=b'?', rest)) => (Some(marker), rest),
+        _ => (None, body),
+    };
+
+    let parameters_end = body
+        .iter()
+        .position(|byte| (0x20..=0x2F).contains(byte))
+        .unwrap_or(body.len());
+    let (parameters, intermediates) = body.split_at(parameters_end);
+    if !intermediates
+        .iter()
+        .all(|byte| (0x20..=0x2F).contains(byte))
+    {
+        return None;
+    }
+
+    let parameters = if parameters.is_empty() {
+        Vec::new()
+    } else {
+        parameters
+            .split(|&byte| byte == b';')
+            .map(|parameter| {
+                parameter
+                    .split(|&byte| byte == b':')
+                    .map(|sub_parameter| {
+                        sub_parameter.iter().try_fold(0u32, |value, &byte| {
+                            if !byte.is_ascii_digit() {
+                                return None;
+                            }
+                            Some(
+                                value
+                                    .saturating_mul(10)
+                                    .saturating_add((byte - b'0') as u32),
+                            )
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?
+    };
+
+    Some(CsiSequence {
+        private_marker,
+        parameters,
+        intermediates: intermediates.to_vec(),
+        final_byte,
+    })
+}
+
+/// Parses a color as terminals report it in OSC responses, the X11 color specification
+/// `rgb:<red>/<green>/<blue>` with 1 to 4 hex digits per component.
+pub(crate) fn parse_rgb_color_spec(spec: &[u8]) -> Option<Color> {
+    let mut components = spec
+        .strip_prefix(b"rgb:")?
+        .split(|&byte| byte == b'/')
+        .map(|digits| {
+            if digits.is_empty() || digits.len() > 4 || !digits.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            let value = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+            // Scale the component to 8 bits, `f`, `ff`, `fff` and `ffff` are all 255.
+            let max = (1 << (4 * digits.len())) - 1;
+            Some(((value * 255 + max / 2) / max) as u8)
+        });
+
+    let color = Color::Rgb {
+        r: components.next()??,
+        g: components.next()??,
+        b: components.next()??,
+    };
+    match components.next() {
+        None => Some(color),
+        Some(_) => None,
+    }
+}
+
+/// Parses the color in the response to an OSC color query, such as `11;rgb:0000/0000/0000`, if
+/// it's the response to the query for `code`.
+pub(crate) fn parse_osc_color_response(data: &[u8], code: &[u8]) -> Option<Color> {
+    parse_rgb_color_spec(data.strip_prefix(code)?.strip_prefix(b";")?)
+}
+
+/// Parses the response to an OSC palette query, such as `4;1;rgb:cdcd/0000/0000`.
+pub(crate) fn parse_osc_palette_response(data: &[u8]) -> Option<(u8, Color)> {
+    let mut parts = data.strip_prefix(b"4;")?.splitn(2, |&byte| byte == b';');
+    let index = std::str::from_utf8(parts.next()?).ok()?.parse().ok()?;
+    let color = parse_rgb_color_spec(parts.next()?)?;
+    Some((index, color))
+}
+
+/// Parses the response to XTVERSION, such as `>|kitty(0.35.2)` or `>|WezTerm 20240203`, into the
+/// name and version of the terminal.
+pub(crate) fn parse_xtversion_response(data: &[u8]) -> Option<(String, Option<String>)> {
+    let text = std::str::from_utf8(data.strip_prefix(b">|")?).ok()?.trim();
+    let (name, version) = match text.strip_suffix(')').and_then(|text| text.split_once('(')) {
+        Some((name, version)) => (name, Some(version)),
+        None => match text.split_once(' ') {
+            Some((name, version)) => (name, Some(version)),
+            None => (text, None),
+        },
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let version = version
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .map(str::to_string);
+    Some((name.to_string(), version))
+}
+
 pub(crate) fn next_parsed<T>(iter: &mut dyn Iterator<Item = &str>) -> io::Result<T>
 where
     T: std::str::FromStr,
@@ -259,6 +427,30 @@ pub(crate) fn parse_csi_cursor_position(buffer: &[u8]) -> io::Result<Option<Inte
     Ok(Some(InternalEvent::CursorPosition(x, y)))
 }
 
+pub(crate) fn parse_csi_window_report(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ 48 ; rows ; columns ; height ; width t
+    //   the in-band resize notification, with the height and width in pixels
+    assert!(buffer.starts_with(b"\x1B[")); // ESC [
+    assert!(buffer.ends_with(b"t"));
+
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+
+    let mut split = s.split(';');
+
+    if next_parsed::<u16>(&mut split)? != 48 {
+        return Ok(None);
+    }
+    let size = WindowSize {
+        rows: next_parsed(&mut split)?,
+        columns: next_parsed(&mut split)?,
+        height: next_parsed(&mut split)?,
+        width: next_parsed(&mut split)?,
+    };
+
+    Ok(Some(InternalEvent::Event(Event::WindowResize(size))))
+}
+
 fn parse_csi_keyboard_enhancement_flags(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
     // ESC [ ? flags u
     assert!(buffer.starts_with(b"\x1B[?")); // ESC [ ?
@@ -268,7 +460,10 @@ fn parse_csi_keyboard_enhancement_flags(buffer: &[u8]) -> io::Result<Option<Inte
         return Ok(None);
     }
 
-    let bits = buffer[3];
+    let bits = std::str::from_utf8(&buffer[3..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?
+        .parse::<u8>()
+        .map_err(|_| could_not_parse_event_error())?;
     let mut flags = KeyboardEnhancementFlags::empty();
 
     if bits & 1 != 0 {
@@ -283,10 +478,9 @@ fn parse_csi_keyboard_enhancement_flags(buffer: &[u8]) -> io::Result<Option<Inte
     if bits & 8 != 0 {
         flags |= KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES;
     }
-    // *Note*: this is not yet supported by crossterm.
-    // if bits & 16 != 0 {
-    //     flags |= KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT;
-    // }
+    if bits & 16 != 0 {
+        flags |= KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT;
+    }
 
     Ok(Some(InternalEvent::KeyboardEnhancementFlags(flags)))
 }
@@ -303,6 +497,28 @@ fn parse_csi_primary_device_attributes(buffer: &[u8]) -> io::Result<Option<Inter
     Ok(Some(InternalEvent::PrimaryDeviceAttributes))
 }
 
+fn parse_csi_secondary_device_attributes(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ > Pp ; Pv ; Pc c
+    assert!(buffer.starts_with(b"\x1B[>"));
+    assert!(buffer.ends_with(b"c"));
+
+    let s = std::str::from_utf8(&buffer[3..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+
+    let mut split = s.split(';');
+    let attributes = SecondaryDeviceAttributes {
+        terminal_type: next_parsed::<u32>(&mut split)?,
+        firmware_version: next_parsed::<u32>(&mut split)?,
+        // Some terminals omit the ROM cartridge registration number.
+        rom_cartridge: match split.next() {
+            Some(value) => value.parse().map_err(|_| could_not_parse_event_error())?,
+            None => 0,
+        },
+    };
+
+    Ok(Some(InternalEvent::SecondaryDeviceAttributes(attributes)))
+}
+
 fn parse_csi_theme_mode(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
     // dark mode:  CSI ? 997 ; 1 n
     // light mode: CSI ? 997 ; 2 n
@@ -452,6 +668,24 @@ pub(crate) fn parse_csi_modifier_key_code(buffer: &[u8]) -> io::Result<Option<In
     Ok(Some(InternalEvent::Event(input_event)))
 }
 
+/// Returns the keypad key for the final byte of an `ESC O` sequence sent in application keypad
+/// mode.
+fn parse_ss3_keypad_key_code(byte: u8) -> Option<KeyCode> {
+    let code = match byte {
+        b'p'..=b'y' => KeyCode::Char((b'0' + byte - b'p') as char),
+        b'M' => KeyCode::Enter,
+        b'j' => KeyCode::Char('*'),
+        b'k' => KeyCode::Char('+'),
+        b'l' => KeyCode::Char(','),
+        b'm' => KeyCode::Char('-'),
+        b'n' => KeyCode::Char('.'),
+        b'o' => KeyCode::Char('/'),
+        b'X' => KeyCode::Char('='),
+        _ => return None,
+    };
+    Some(code)
+}
+
 fn translate_functional_key_code(codepoint: u32) -> Option<(KeyCode, KeyEventState)> {
     if let Some(keycode) = match codepoint {
         57399 => Some(KeyCode::Char('0')),
@@ -553,6 +787,28 @@ fn translate_functional_key_code(codepoint: u32) -> Option<(KeyCode, KeyEventSta
     None
 }
 
+// Translates the character of a `CSI u` or modifyOtherKeys sequence to a key code.
+fn char_to_key_code(c: char, modifiers: KeyModifiers) -> KeyCode {
+    match c {
+        '\x1B' => KeyCode::Esc,
+        '\r' => KeyCode::Enter,
+        // Issue #371: \n = 0xA, which is also the keycode for Ctrl+J. The only reason we get
+        // newlines as input is because the terminal converts \r into \n for us. When we
+        // enter raw mode, we disable that, so \n no longer has any meaning - it's better to
+        // use Ctrl+J. Waiting to handle it here means it gets picked up later
+        '\n' if !crate::terminal::sys::is_raw_mode_enabled() => KeyCode::Enter,
+        '\t' => {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                KeyCode::BackTab
+            } else {
+                KeyCode::Tab
+            }
+        }
+        '\x7F' => KeyCode::Backspace,
+        _ => KeyCode::Char(c),
+    }
+}
+
 pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
     assert!(buffer.starts_with(b"\x1B[")); // ESC [
     assert!(buffer.ends_with(b"u"));
@@ -600,27 +856,7 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<I
         if let Some((special_key_code, state)) = translate_functional_key_code(codepoint) {
             (special_key_code, state)
         } else if let Some(c) = char::from_u32(codepoint) {
-            (
-                match c {
-                    '\x1B' => KeyCode::Esc,
-                    '\r' => KeyCode::Enter,
-                    // Issue #371: \n = 0xA, which is also the keycode for Ctrl+J. The only reason we get
-                    // newlines as input is because the terminal converts \r into \n for us. When we
-                    // enter raw mode, we disable that, so \n no longer has any meaning - it's better to
-                    // use Ctrl+J. Waiting to handle it here means it gets picked up later
-                    '\n' if !crate::terminal::sys::is_raw_mode_enabled() => KeyCode::Enter,
-                    '\t' => {
-                        if modifiers.contains(KeyModifiers::SHIFT) {
-                            KeyCode::BackTab
-                        } else {
-                            KeyCode::Tab
-                        }
-                    }
-                    '\x7F' => KeyCode::Backspace,
-                    _ => KeyCode::Char(c),
-                },
-                KeyEventState::empty(),
-            )
+            (char_to_key_code(c, modifiers), KeyEventState::empty())
         } else {
             return Err(could_not_parse_event_error());
         }
@@ -650,29 +886,56 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<I
         }
     }
 
-    // When the "report alternate keys" flag is enabled in the Kitty Keyboard Protocol
-    // and the terminal sends a keyboard event containing shift, the sequence will
-    // contain an additional codepoint separated by a ':' character which contains
-    // the shifted character according to the keyboard layout.
-    if modifiers.contains(KeyModifiers::SHIFT) {
-        if let Some(shifted_c) = codepoints
+    // When the "report alternate keys" flag is enabled in the Kitty Keyboard Protocol, the
+    // key code may be followed by the shifted key (if shift is pressed) and the key in the
+    // base (PC-101) layout, separated by ':' characters. Either of them may be empty.
+    let mut alternate_key = || {
+        codepoints
             .next()
             .and_then(|codepoint| codepoint.parse::<u32>().ok())
             .and_then(char::from_u32)
-        {
-            keycode = KeyCode::Char(shifted_c);
-            modifiers.set(KeyModifiers::SHIFT, false);
-        }
+            .map(KeyCode::Char)
+    };
+    let shifted_keycode = alternate_key().filter(|_| modifiers.contains(KeyModifiers::SHIFT));
+    let base_layout_keycode = alternate_key();
+    let alternate_keys = if shifted_keycode.is_some() || base_layout_keycode.is_some() {
+        Some(AlternateKeys {
+            unshifted: keycode,
+            shifted: shifted_keycode,
+            base_layout: base_layout_keycode,
+        })
+    } else {
+        None
+    };
+
+    // The shifted key overrides the key code, with the shift already applied.
+    if let Some(shifted_keycode) = shifted_keycode {
+        keycode = shifted_keycode;
+        modifiers.set(KeyModifiers::SHIFT, false);
     }
 
-    let input_event = Event::Key(KeyEvent::new_with_kind_and_state(
+    let mut key_event = KeyEvent::new_with_kind_and_state(
         keycode,
         modifiers,
         kind,
         state_from_keycode | state_from_modifiers,
-    ));
+    );
 
-    Ok(Some(InternalEvent::Event(input_event)))
+    // When the "report associated text" flag is enabled in the Kitty Keyboard Protocol, the
+    // third field contains the text produced by the key as colon-separated codepoints. Text
+    // that can't be decoded or is too long is ignored rather than dropping the key event.
+    key_event.alternate_keys = alternate_keys;
+    key_event.text = split
+        .next()
+        .filter(|text| !text.is_empty())
+        .and_then(|text| {
+            text.split(':')
+                .map(|codepoint| codepoint.parse::<u32>().ok().and_then(char::from_u32))
+                .collect::<Option<String>>()
+        })
+        .and_then(|text| KeyText::new(&text));
+
+    Ok(Some(InternalEvent::Event(Event::Key(key_event))))
 }
 
 pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
@@ -686,6 +949,10 @@ pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<Int
     // This CSI sequence can be a list of semicolon-separated numbers.
     let first = next_parsed::<u8>(&mut split)?;
 
+    if first == 27 {
+        return parse_csi_modify_other_keys(&mut split);
+    }
+
     let (modifiers, kind, state) =
         if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
             (
@@ -719,6 +986,242 @@ pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<Int
     Ok(Some(InternalEvent::Event(input_event)))
 }
 
+fn parse_csi_modify_other_keys(
+    split: &mut dyn Iterator<Item = &str>,
+) -> io::Result<Option<InternalEvent>> {
+    // xterm's modifyOtherKeys encoding (the first parameter, 27, is already consumed):
+    //
+    //     CSI 27 ; modifiers ; codepoint ~
+    let (modifier_mask, _) = modifier_and_kind_parsed(split)?;
+    let modifiers = parse_modifiers(modifier_mask);
+    let c = char::from_u32(next_parsed::<u32>(split)?).ok_or_else(could_not_parse_event_error)?;
+
+    let input_event = Event::Key(KeyEvent::new_with_kind_and_state(
+        char_to_key_code(c, modifiers),
+        modifiers,
+        KeyEventKind::Press,
+        parse_modifiers_to_state(modifier_mask),
+    ));
+
+    Ok(Some(InternalEvent::Event(input_event)))
+}
+
+/// Parses a key event record of the win32-input-mode:
+///
+/// ```text
+/// CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _
+/// ```
+///
+/// The parameters are the virtual key code, the scan code, the Unicode character, whether the key
+/// is down, the control key state and the repeat count. Any of them may be omitted, which means 0
+/// (1 for the repeat count).
+pub(crate) fn parse_csi_win32_input_mode(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(b"\x1B[")); // ESC [
+    assert!(buffer.ends_with(b"_"));
+
+    const VK_BACK: u32 = 0x08;
+    const VK_TAB: u32 = 0x09;
+    const VK_CLEAR: u32 = 0x0C;
+    const VK_RETURN: u32 = 0x0D;
+    const VK_SHIFT: u32 = 0x10;
+    const VK_CONTROL: u32 = 0x11;
+    const VK_MENU: u32 = 0x12;
+    const VK_PAUSE: u32 = 0x13;
+    const VK_CAPITAL: u32 = 0x14;
+    const VK_ESCAPE: u32 = 0x1B;
+    const VK_PRIOR: u32 = 0x21;
+    const VK_NEXT: u32 = 0x22;
+    const VK_END: u32 = 0x23;
+    const VK_HOME: u32 = 0x24;
+    const VK_LEFT: u32 = 0x25;
+    const VK_UP: u32 = 0x26;
+    const VK_RIGHT: u32 = 0x27;
+    const VK_DOWN: u32 = 0x28;
+    const VK_SNAPSHOT: u32 = 0x2C;
+    const VK_INSERT: u32 = 0x2D;
+    const VK_DELETE: u32 = 0x2E;
+    const VK_LWIN: u32 = 0x5B;
+    const VK_RWIN: u32 = 0x5C;
+    const VK_APPS: u32 = 0x5D;
+    const VK_NUMPAD0: u32 = 0x60;
+    const VK_DIVIDE: u32 = 0x6F;
+    const VK_F1: u32 = 0x70;
+    const VK_F24: u32 = 0x87;
+    const VK_NUMLOCK: u32 = 0x90;
+    const VK_SCROLL: u32 = 0x91;
+    const VK_VOLUME_MUTE: u32 = 0xAD;
+    const VK_VOLUME_DOWN: u32 = 0xAE;
+    const VK_VOLUME_UP: u32 = 0xAF;
+    const VK_MEDIA_NEXT_TRACK: u32 = 0xB0;
+    const VK_MEDIA_PREV_TRACK: u32 = 0xB1;
+    const VK_MEDIA_STOP: u32 = 0xB2;
+    const VK_MEDIA_PLAY_PAUSE: u32 = 0xB3;
+    const VK_OEM_1: u32 = 0xBA;
+    const VK_OEM_PLUS: u32 = 0xBB;
+    const VK_OEM_COMMA: u32 = 0xBC;
+    const VK_OEM_MINUS: u32 = 0xBD;
+    const VK_OEM_PERIOD: u32 = 0xBE;
+    const VK_OEM_2: u32 = 0xBF;
+    const VK_OEM_3: u32 = 0xC0;
+    const VK_OEM_4: u32 = 0xDB;
+    const VK_OEM_5: u32 = 0xDC;
+    const VK_OEM_6: u32 = 0xDD;
+    const VK_OEM_7: u32 = 0xDE;
+
+    // Control key state flags.
+    const RIGHT_ALT_PRESSED: u32 = 0x0001;
+    const LEFT_ALT_PRESSED: u32 = 0x0002;
+    const RIGHT_CTRL_PRESSED: u32 = 0x0004;
+    const LEFT_CTRL_PRESSED: u32 = 0x0008;
+    const SHIFT_PRESSED: u32 = 0x0010;
+    const NUMLOCK_ON: u32 = 0x0020;
+    const CAPSLOCK_ON: u32 = 0x0080;
+    const ENHANCED_KEY: u32 = 0x0100;
+
+    // The scan code of the right Shift key, which has no enhanced flag.
+    const RIGHT_SHIFT_SCAN_CODE: u32 = 0x36;
+
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut parameters = [0, 0, 0, 0, 0, 1];
+    for (index, parameter) in s.split(';').enumerate() {
+        let value = parameters
+            .get_mut(index)
+            .ok_or_else(could_not_parse_event_error)?;
+        if !parameter.is_empty() {
+            *value = parameter
+                .parse::<u32>()
+                .map_err(|_| could_not_parse_event_error())?;
+        }
+    }
+    let [virtual_key_code, scan_code, unicode_char, key_down, control_key_state, repeat_count] =
+        parameters;
+    let has_state = |flags: u32| control_key_state & flags != 0;
+
+    let mut modifiers = KeyModifiers::empty();
+    if has_state(SHIFT_PRESSED) {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if has_state(LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED) {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+    if has_state(LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED) {
+        modifiers |= KeyModifiers::ALT;
+    }
+
+    let mut state = KeyEventState::empty();
+    if has_state(CAPSLOCK_ON) {
+        state |= KeyEventState::CAPS_LOCK;
+    }
+    if has_state(NUMLOCK_ON) {
+        state |= KeyEventState::NUM_LOCK;
+    }
+
+    let kind = if key_down == 0 {
+        KeyEventKind::Release
+    } else if repeat_count > 1 {
+        KeyEventKind::Repeat
+    } else {
+        KeyEventKind::Press
+    };
+
+    // Releasing Alt after typing an Alt code (Alt + numpad digits) carries the typed character.
+    if virtual_key_code == VK_MENU && key_down == 0 && unicode_char != 0 {
+        let c = char::from_u32(unicode_char).ok_or_else(could_not_parse_event_error)?;
+        let key_event = KeyEvent::new_with_kind_and_state(
+            KeyCode::Char(c),
+            modifiers,
+            KeyEventKind::Press,
+            state,
+        );
+        return Ok(Some(InternalEvent::Event(Event::Key(key_event))));
+    }
+
+    let enhanced = has_state(ENHANCED_KEY);
+    let code = match virtual_key_code {
+        VK_SHIFT if scan_code == RIGHT_SHIFT_SCAN_CODE => {
+            KeyCode::Modifier(ModifierKeyCode::RightShift)
+        }
+        VK_SHIFT => KeyCode::Modifier(ModifierKeyCode::LeftShift),
+        VK_CONTROL if enhanced => KeyCode::Modifier(ModifierKeyCode::RightControl),
+        VK_CONTROL => KeyCode::Modifier(ModifierKeyCode::LeftControl),
+        VK_MENU if enhanced => KeyCode::Modifier(ModifierKeyCode::RightAlt),
+        VK_MENU => KeyCode::Modifier(ModifierKeyCode::LeftAlt),
+        VK_LWIN => KeyCode::Modifier(ModifierKeyCode::LeftSuper),
+        VK_RWIN => KeyCode::Modifier(ModifierKeyCode::RightSuper),
+        VK_BACK => KeyCode::Backspace,
+        VK_TAB if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        VK_TAB => KeyCode::Tab,
+        VK_CLEAR => KeyCode::KeypadBegin,
+        VK_RETURN => KeyCode::Enter,
+        VK_PAUSE => KeyCode::Pause,
+        VK_CAPITAL => KeyCode::CapsLock,
+        VK_ESCAPE => KeyCode::Esc,
+        VK_PRIOR => KeyCode::PageUp,
+        VK_NEXT => KeyCode::PageDown,
+        VK_END => KeyCode::End,
+        VK_HOME => KeyCode::Home,
+        VK_LEFT => KeyCode::Left,
+        VK_UP => KeyCode::Up,
+        VK_RIGHT => KeyCode::Right,
+        VK_DOWN => KeyCode::Down,
+        VK_SNAPSHOT => KeyCode::PrintScreen,
+        VK_INSERT => KeyCode::Insert,
+        VK_DELETE => KeyCode::Delete,
+        VK_APPS => KeyCode::Menu,
+        VK_F1..=VK_F24 => KeyCode::F((virtual_key_code - VK_F1 + 1) as u8),
+        VK_NUMLOCK => KeyCode::NumLock,
+        VK_SCROLL => KeyCode::ScrollLock,
+        VK_VOLUME_MUTE => KeyCode::Media(MediaKeyCode::MuteVolume),
+        VK_VOLUME_DOWN => KeyCode::Media(MediaKeyCode::LowerVolume),
+        VK_VOLUME_UP => KeyCode::Media(MediaKeyCode::RaiseVolume),
+        VK_MEDIA_NEXT_TRACK => KeyCode::Media(MediaKeyCode::TrackNext),
+        VK_MEDIA_PREV_TRACK => KeyCode::Media(MediaKeyCode::TrackPrevious),
+        VK_MEDIA_STOP => KeyCode::Media(MediaKeyCode::Stop),
+        VK_MEDIA_PLAY_PAUSE => KeyCode::Media(MediaKeyCode::PlayPause),
+        // Control characters are sent for keys pressed with Ctrl, use the key instead. Virtual
+        // key codes of letters and digits are their uppercase ASCII characters.
+        _ if unicode_char < 0x20 => match char::from_u32(virtual_key_code) {
+            Some(c @ ('A'..='Z' | '0'..='9')) => {
+                if modifiers.contains(KeyModifiers::SHIFT)
+                    ^ state.contains(KeyEventState::CAPS_LOCK)
+                {
+                    KeyCode::Char(c)
+                } else {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                }
+            }
+            // Punctuation keys pressed with Ctrl, named by their character in the US layout.
+            _ if modifiers.contains(KeyModifiers::CONTROL) => match virtual_key_code {
+                VK_OEM_1 => KeyCode::Char(';'),
+                VK_OEM_PLUS => KeyCode::Char('='),
+                VK_OEM_COMMA => KeyCode::Char(','),
+                VK_OEM_MINUS => KeyCode::Char('-'),
+                VK_OEM_PERIOD => KeyCode::Char('.'),
+                VK_OEM_2 => KeyCode::Char('/'),
+                VK_OEM_3 => KeyCode::Char('`'),
+                VK_OEM_4 => KeyCode::Char('['),
+                VK_OEM_5 => KeyCode::Char('\\'),
+                VK_OEM_6 => KeyCode::Char(']'),
+                VK_OEM_7 => KeyCode::Char('\''),
+                _ => return Ok(None),
+            },
+            // A dead key or a key without a character.
+            _ => return Ok(None),
+        },
+        // Characters outside of the Basic Multilingual Plane are sent as two records with a
+        // surrogate each, which can't be decoded on their own.
+        _ => KeyCode::Char(char::from_u32(unicode_char).ok_or_else(could_not_parse_event_error)?),
+    };
+
+    if (VK_NUMPAD0..=VK_DIVIDE).contains(&virtual_key_code) {
+        state |= KeyEventState::KEYPAD;
+    }
+
+    let key_event = KeyEvent::new_with_kind_and_state(code, modifiers, kind, state);
+    Ok(Some(InternalEvent::Event(Event::Key(key_event))))
+}
+
 pub(crate) fn parse_csi_rxvt_mouse(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
     // rxvt mouse encoding:
     // ESC [ Cb ; Cx ; Cy ; M
@@ -743,6 +1246,7 @@ pub(crate) fn parse_csi_rxvt_mouse(buffer: &[u8]) -> io::Result<Option<InternalE
         column: cx,
         row: cy,
         modifiers,
+        position_unit: MousePositionUnit::Cell,
     }))))
 }
 
@@ -771,6 +1275,40 @@ pub(crate) fn parse_csi_normal_mouse(buffer: &[u8]) -> io::Result<Option<Interna
         column: cx,
         row: cy,
         modifiers,
+        position_unit: MousePositionUnit::Cell,
+    }))))
+}
+
+pub(crate) fn parse_csi_utf8_mouse(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // UTF-8 mouse encoding: ESC [ M CB Cx Cy, like the normal encoding, but every value is
+    // a UTF-8 encoded character to allow values above 223.
+
+    assert!(buffer.starts_with(b"\x1B[M")); // ESC [ M
+
+    let s = std::str::from_utf8(&buffer[3..]).map_err(|_| could_not_parse_event_error())?;
+    let mut values = s.chars().map(|c| (c as u32).checked_sub(32));
+    let mut next_value = || {
+        values
+            .next()
+            .flatten()
+            .and_then(|value| u16::try_from(value).ok())
+            .ok_or_else(could_not_parse_event_error)
+    };
+
+    let cb = u8::try_from(next_value()?).map_err(|_| could_not_parse_event_error())?;
+    let (kind, modifiers) = parse_cb(cb)?;
+
+    // The upper left character position on the terminal is denoted as 1,1.
+    // Subtract 1 to keep it synced with cursor
+    let cx = next_value()?.saturating_sub(1);
+    let cy = next_value()?.saturating_sub(1);
+
+    Ok(Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+        kind,
+        column: cx,
+        row: cy,
+        modifiers,
+        position_unit: MousePositionUnit::Cell,
     }))))
 }
 
@@ -792,9 +1330,10 @@ pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<InternalEv
 
     // See http://www.xfree86.org/current/ctlseqs.html#Mouse%20Tracking
     // The upper left character position on the terminal is denoted as 1,1.
-    // Subtract 1 to keep it synced with cursor
-    let cx = next_parsed::<u16>(&mut split)? - 1;
-    let cy = next_parsed::<u16>(&mut split)? - 1;
+    // Subtract 1 to keep it synced with cursor. In SGR-Pixels mode some terminals start
+    // counting pixels at 0.
+    let cx = next_parsed::<u16>(&mut split)?.saturating_sub(1);
+    let cy = next_parsed::<u16>(&mut split)?.saturating_sub(1);
 
     // When button 3 in Cb is used to represent mouse release, you can't tell which button was
     // released. SGR mode solves this by having the sequence end with a lowercase m if it's a
@@ -816,6 +1355,7 @@ pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<InternalEv
         column: cx,
         row: cy,
         modifiers,
+        position_unit: MousePositionUnit::Cell,
     }))))
 }
 
@@ -849,6 +1389,14 @@ fn parse_cb(cb: u8) -> io::Result<(MouseEventKind, KeyModifiers)> {
         (5, false) => MouseEventKind::ScrollDown,
         (6, false) => MouseEventKind::ScrollLeft,
         (7, false) => MouseEventKind::ScrollRight,
+        (8, false) => MouseEventKind::Down(MouseButton::Back),
+        (9, false) => MouseEventKind::Down(MouseButton::Forward),
+        (10, false) => MouseEventKind::Down(MouseButton::Button10),
+        (11, false) => MouseEventKind::Down(MouseButton::Button11),
+        (8, true) => MouseEventKind::Drag(MouseButton::Back),
+        (9, true) => MouseEventKind::Drag(MouseButton::Forward),
+        (10, true) => MouseEventKind::Drag(MouseButton::Button10),
+        (11, true) => MouseEventKind::Drag(MouseButton::Button11),
         // We do not support other buttons.
         _ => return Err(could_not_parse_event_error()),
     };
@@ -1021,6 +1569,7 @@ mod tests {
                 column: 29,
                 row: 39,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
 
@@ -1032,6 +1581,7 @@ mod tests {
                 column: 63,
                 row: 79,
                 modifiers: KeyModifiers::CONTROL,
+                position_unit: MousePositionUnit::Cell,
             })))
         );
 
@@ -1043,6 +1593,7 @@ mod tests {
                 column: 19,
                 row: 9,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
 
@@ -1056,6 +1607,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_ss3_keypad_keys() {
+        let keypad_key = |code| {
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(
+                    code,
+                    KeyModifiers::empty(),
+                    KeyEventKind::Press,
+                    KeyEventState::KEYPAD,
+                ),
+            )))
+        };
+
+        assert_eq!(
+            parse_event(b"\x1BOp", false).unwrap(),
+            keypad_key(KeyCode::Char('0'))
+        );
+        assert_eq!(
+            parse_event(b"\x1BOy", false).unwrap(),
+            keypad_key(KeyCode::Char('9'))
+        );
+        assert_eq!(
+            parse_event(b"\x1BOM", false).unwrap(),
+            keypad_key(KeyCode::Enter)
+        );
+        assert_eq!(
+            parse_event(b"\x1BOk", false).unwrap(),
+            keypad_key(KeyCode::Char('+'))
+        );
+        assert_eq!(
+            parse_event(b"\x1BOX", false).unwrap(),
+            keypad_key(KeyCode::Char('='))
+        );
+        // The cursor keys in application cursor keys mode.
+        assert_eq!(
+            parse_event(b"\x1BOA", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Up.into()))),
+        );
+        assert!(parse_event(b"\x1BOz", false).is_err());
+    }
+
     #[test]
     fn test_parse_event() {
         assert_eq!(
@@ -1080,6 +1672,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_control_string() {
+        assert_eq!(
+            parse_event(b"\x1B]11;rgb:0000/0000/0000\x07", false).unwrap(),
+            Some(InternalEvent::OperatingSystemCommand(
+                b"11;rgb:0000/0000/0000".to_vec()
+            )),
+        );
+        assert_eq!(
+            parse_event(b"\x1BP>|kitty(0.31.0)\x1B\\", false).unwrap(),
+            Some(InternalEvent::DeviceControlString(
+                b">|kitty(0.31.0)".to_vec()
+            )),
+        );
+        assert_eq!(
+            parse_event(b"\x1B_Gi=1;OK\x1B\\", false).unwrap(),
+            Some(InternalEvent::ApplicationProgramCommand(
+                b"Gi=1;OK".to_vec()
+            )),
+        );
+        assert_eq!(parse_event(b"\x1B]11;rgb:00", true).unwrap(), None);
+        // Without any data, these are still Alt modified keys.
+        assert_eq!(
+            parse_event(b"\x1BP", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('P'),
+                KeyModifiers::ALT | KeyModifiers::SHIFT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_rgb_color_spec() {
+        assert_eq!(
+            parse_rgb_color_spec(b"rgb:ffff/8080/0000"),
+            Some(Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0
+            })
+        );
+        assert_eq!(
+            parse_rgb_color_spec(b"rgb:f/8/0"),
+            Some(Color::Rgb {
+                r: 255,
+                g: 136,
+                b: 0
+            })
+        );
+        assert_eq!(
+            parse_rgb_color_spec(b"rgb:1e/1e2/1e1e"),
+            Some(Color::Rgb {
+                r: 30,
+                g: 30,
+                b: 30
+            })
+        );
+        assert_eq!(parse_rgb_color_spec(b"rgb:ff/ff"), None);
+        assert_eq!(parse_rgb_color_spec(b"rgb:ff/ff/ff/ff"), None);
+        assert_eq!(parse_rgb_color_spec(b"rgb:ff//ff"), None);
+        assert_eq!(parse_rgb_color_spec(b"rgb:fffff/ff/ff"), None);
+        assert_eq!(parse_rgb_color_spec(b"rgb:gg/ff/ff"), None);
+        assert_eq!(parse_rgb_color_spec(b"rgb:+f/ff/ff"), None);
+        assert_eq!(parse_rgb_color_spec(b"#ffffff"), None);
+    }
+
+    #[test]
+    fn test_parse_osc_color_responses() {
+        let black = Color::Rgb { r: 0, g: 0, b: 0 };
+        assert_eq!(
+            parse_osc_color_response(b"11;rgb:0000/0000/0000", b"11"),
+            Some(black)
+        );
+        assert_eq!(
+            parse_osc_color_response(b"11;rgb:0000/0000/0000", b"10"),
+            None
+        );
+        assert_eq!(
+            parse_osc_color_response(b"110;rgb:0000/0000/0000", b"11"),
+            None
+        );
+
+        assert_eq!(
+            parse_osc_palette_response(b"4;255;rgb:0/0/0"),
+            Some((255, black))
+        );
+        assert_eq!(parse_osc_palette_response(b"4;256;rgb:0/0/0"), None);
+        assert_eq!(parse_osc_palette_response(b"4;1;?"), None);
+        assert_eq!(parse_osc_palette_response(b"11;rgb:0/0/0"), None);
+    }
+
+    #[test]
+    fn test_parse_csi_secondary_device_attributes() {
+        assert_eq!(
+            parse_event(b"\x1B[>41;390;0c", false).unwrap(),
+            Some(InternalEvent::SecondaryDeviceAttributes(
+                SecondaryDeviceAttributes {
+                    terminal_type: 41,
+                    firmware_version: 390,
+                    rom_cartridge: 0,
+                }
+            )),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[>65;7600c", false).unwrap(),
+            Some(InternalEvent::SecondaryDeviceAttributes(
+                SecondaryDeviceAttributes {
+                    terminal_type: 65,
+                    firmware_version: 7600,
+                    rom_cartridge: 0,
+                }
+            )),
+        );
+        assert!(parse_event(b"\x1B[>c", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_xtversion_response() {
+        let identity = |name: &str, version: Option<&str>| {
+            Some((name.to_string(), version.map(str::to_string)))
+        };
+        assert_eq!(
+            parse_xtversion_response(b">|kitty(0.35.2)"),
+            identity("kitty", Some("0.35.2"))
+        );
+        assert_eq!(
+            parse_xtversion_response(b">|XTerm(390)"),
+            identity("XTerm", Some("390"))
+        );
+        assert_eq!(
+            parse_xtversion_response(b">|WezTerm 20240203-110809-5046fc22"),
+            identity("WezTerm", Some("20240203-110809-5046fc22"))
+        );
+        assert_eq!(parse_xtversion_response(b">|foot"), identity("foot", None));
+        assert_eq!(parse_xtversion_response(b">|"), None);
+        assert_eq!(parse_xtversion_response(b"1$r0m"), None);
+    }
+
+    #[test]
+    fn test_parse_csi_sequence() {
+        assert_eq!(
+            parse_csi_sequence(b"\x1B[?1;;2:34:5$p"),
+            Some(CsiSequence {
+                private_marker: Some(b'?'),
+                parameters: vec![vec![1], vec![0], vec![2, 34, 5]],
+                intermediates: b"$".to_vec(),
+                final_byte: b'p',
+            })
+        );
+        assert_eq!(
+            parse_csi_sequence(b"\x1B[X"),
+            Some(CsiSequence {
+                private_marker: None,
+                parameters: vec![],
+                intermediates: vec![],
+                final_byte: b'X',
+            })
+        );
+        assert_eq!(parse_csi_sequence(b"\x1B[1 2X"), None);
+        assert_eq!(parse_csi_sequence(b"\x1B[1?X"), None);
+        assert_eq!(parse_csi_sequence(b"\x1B[1"), None);
+        assert_eq!(parse_csi_sequence(b"\x1BOP"), None);
+    }
+
     #[test]
     fn test_parse_csi_modifier_key_code() {
         assert_eq!(
@@ -1147,6 +1903,7 @@ mod tests {
                 column: 29,
                 row: 39,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
     }
@@ -1160,6 +1917,7 @@ mod tests {
                 column: 63,
                 row: 79,
                 modifiers: KeyModifiers::CONTROL,
+                position_unit: MousePositionUnit::Cell,
             })))
         );
     }
@@ -1173,6 +1931,7 @@ mod tests {
                 column: 19,
                 row: 9,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
         assert_eq!(
@@ -1182,6 +1941,7 @@ mod tests {
                 column: 19,
                 row: 9,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
         assert_eq!(
@@ -1191,6 +1951,7 @@ mod tests {
                 column: 19,
                 row: 9,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
         assert_eq!(
@@ -1200,10 +1961,41 @@ mod tests {
                 column: 19,
                 row: 9,
                 modifiers: KeyModifiers::empty(),
+                position_unit: MousePositionUnit::Cell,
             })))
         );
     }
 
+    #[test]
+    fn test_parse_extra_mouse_buttons() {
+        let mouse_kind = |event: Option<InternalEvent>| match event {
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent { kind, .. }))) => kind,
+            event => panic!("unexpected event {:?}", event),
+        };
+
+        assert_eq!(
+            mouse_kind(parse_csi_sgr_mouse(b"\x1B[<128;1;1M").unwrap()),
+            MouseEventKind::Down(MouseButton::Back)
+        );
+        assert_eq!(
+            mouse_kind(parse_csi_sgr_mouse(b"\x1B[<129;1;1m").unwrap()),
+            MouseEventKind::Up(MouseButton::Forward)
+        );
+        assert_eq!(
+            mouse_kind(parse_csi_sgr_mouse(b"\x1B[<162;1;1M").unwrap()),
+            MouseEventKind::Drag(MouseButton::Button10)
+        );
+        assert_eq!(
+            mouse_kind(parse_csi_normal_mouse(b"\x1B[M\xA3!!").unwrap()),
+            MouseEventKind::Down(MouseButton::Button11)
+        );
+        assert_eq!(
+            mouse_kind(parse_csi_rxvt_mouse(b"\x1B[161;1;1M").unwrap()),
+            MouseEventKind::Down(MouseButton::Forward)
+        );
+        assert!(parse_csi_sgr_mouse(b"\x1B[<192;1;1M").is_err());
+    }
+
     #[test]
     fn test_utf8() {
         // https://www.php.net/manual/en/reference.pcre.pattern.modifiers.php#54805
@@ -1503,6 +2295,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_csi_u_encoded_key_code_with_associated_text() {
+        let parse_text = |buffer: &[u8]| match parse_csi_u_encoded_key_code(buffer).unwrap() {
+            Some(InternalEvent::Event(Event::Key(key_event))) => key_event.text.map(String::from),
+            event => panic!("unexpected event {:?}", event),
+        };
+        assert_eq!(parse_text(b"\x1B[97;;97u"), Some("a".to_string()));
+        assert_eq!(parse_text(b"\x1B[97;2;65u"), Some("A".to_string()));
+        assert_eq!(
+            parse_text(b"\x1B[101;1;101:769u"),
+            Some("e\u{301}".to_string())
+        );
+        assert_eq!(parse_text(b"\x1B[97;5u"), None);
+        assert_eq!(parse_text(b"\x1B[97;1;xu"), None);
+        // Text longer than `KeyText::CAPACITY` isn't reported.
+        assert_eq!(
+            parse_text(b"\x1B[97;1;97:97:97:97:97:97:97:97:97:97:97:97:97:97:97:97u"),
+            Some("a".repeat(16))
+        );
+        assert_eq!(
+            parse_text(b"\x1B[97;1;97:97:97:97:97:97:97:97:97:97:97:97:97:97:97:97:97u"),
+            None
+        );
+
+        // The text doesn't affect equality.
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;;97u").unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Char('a').into()))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_keyboard_enhancement_flags() {
+        assert_eq!(
+            parse_event(b"\x1B[?1u", false).unwrap(),
+            Some(InternalEvent::KeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+            )),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[?31u", false).unwrap(),
+            Some(InternalEvent::KeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::all()
+            )),
+        );
+    }
+
     #[test]
     fn test_parse_csi_u_with_shifted_keycode() {
         assert_eq!(
@@ -1523,6 +2362,212 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_csi_u_with_alternate_keys() {
+        let parse_key_event = |buffer: &[u8]| match parse_event(buffer, false).unwrap() {
+            Some(InternalEvent::Event(Event::Key(key_event))) => key_event,
+            event => panic!("unexpected event {:?}", event),
+        };
+
+        // S-9 with a US layout.
+        let key_event = parse_key_event(b"\x1B[57:40:57;2u");
+        assert_eq!(
+            key_event.alternate_keys,
+            Some(AlternateKeys {
+                unshifted: KeyCode::Char('9'),
+                shifted: Some(KeyCode::Char('(')),
+                base_layout: Some(KeyCode::Char('9')),
+            })
+        );
+        assert_eq!(
+            key_event,
+            KeyEvent::new(KeyCode::Char('('), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            key_event.unshifted(),
+            KeyEvent::new(KeyCode::Char('9'), KeyModifiers::SHIFT)
+        );
+
+        // C-с with a Russian layout, without a shifted key.
+        let key_event = parse_key_event(b"\x1B[1089::99;5u");
+        assert_eq!(
+            key_event,
+            KeyEvent::new(KeyCode::Char('с'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(key_event.unshifted(), key_event);
+        assert_eq!(
+            key_event.base_layout(),
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+
+        assert_eq!(parse_key_event(b"\x1B[97;5u").alternate_keys, None);
+    }
+
+    #[test]
+    fn test_parse_csi_win32_input_mode() {
+        let key = |code, modifiers, kind, state| {
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(code, modifiers, kind, state),
+            )))
+        };
+
+        // 'a' down and up.
+        assert_eq!(
+            parse_event(b"\x1B[65;30;97;1;0;1_", false).unwrap(),
+            key(
+                KeyCode::Char('a'),
+                KeyModifiers::NONE,
+                KeyEventKind::Press,
+                KeyEventState::NONE
+            ),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[65;30;97;0;0;1_", false).unwrap(),
+            key(
+                KeyCode::Char('a'),
+                KeyModifiers::NONE,
+                KeyEventKind::Release,
+                KeyEventState::NONE
+            ),
+        );
+        // Ctrl+Shift+A sends a control character, with Caps Lock on.
+        assert_eq!(
+            parse_event(b"\x1B[65;30;1;1;152;1_", false).unwrap(),
+            key(
+                KeyCode::Char('a'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                KeyEventKind::Press,
+                KeyEventState::CAPS_LOCK
+            ),
+        );
+        // Right Alt+F5, repeated.
+        assert_eq!(
+            parse_event(b"\x1B[116;63;0;1;1;3_", false).unwrap(),
+            key(
+                KeyCode::F(5),
+                KeyModifiers::ALT,
+                KeyEventKind::Repeat,
+                KeyEventState::NONE
+            ),
+        );
+        // Right Ctrl, an enhanced key.
+        assert_eq!(
+            parse_event(b"\x1B[17;29;0;1;268;1_", false).unwrap(),
+            key(
+                KeyCode::Modifier(ModifierKeyCode::RightControl),
+                KeyModifiers::CONTROL,
+                KeyEventKind::Press,
+                KeyEventState::NONE
+            ),
+        );
+        // Numpad 5 with Num Lock on.
+        assert_eq!(
+            parse_event(b"\x1B[101;76;53;1;32;1_", false).unwrap(),
+            key(
+                KeyCode::Char('5'),
+                KeyModifiers::NONE,
+                KeyEventKind::Press,
+                KeyEventState::KEYPAD | KeyEventState::NUM_LOCK
+            ),
+        );
+        // A character without a virtual key code, as sent for pasted text.
+        assert_eq!(
+            parse_event(b"\x1B[;;233;1;;_", false).unwrap(),
+            key(
+                KeyCode::Char('\u{e9}'),
+                KeyModifiers::NONE,
+                KeyEventKind::Press,
+                KeyEventState::NONE
+            ),
+        );
+        // Ctrl+[ and Ctrl+] send control characters as well.
+        assert_eq!(
+            parse_event(b"\x1B[219;26;27;1;8;1_", false).unwrap(),
+            key(
+                KeyCode::Char('['),
+                KeyModifiers::CONTROL,
+                KeyEventKind::Press,
+                KeyEventState::NONE
+            ),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[221;27;29;1;8;1_", false).unwrap(),
+            key(
+                KeyCode::Char(']'),
+                KeyModifiers::CONTROL,
+                KeyEventKind::Press,
+                KeyEventState::NONE
+            ),
+        );
+        // A dead key.
+        assert_eq!(parse_event(b"\x1B[222;40;0;1;0;1_", false).unwrap(), None);
+        assert!(parse_event(b"\x1B[1;2;3;4;5;6;7_", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_csi_window_report() {
+        assert_eq!(
+            parse_event(b"\x1B[48;24;80;480;800t", false).unwrap(),
+            Some(InternalEvent::Event(Event::WindowResize(WindowSize {
+                rows: 24,
+                columns: 80,
+                width: 800,
+                height: 480,
+            }))),
+        );
+        // Other window reports, like the text area size in characters, are ignored.
+        assert_eq!(parse_event(b"\x1B[8;24;80t", false).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_csi_modify_other_keys() {
+        assert_eq!(
+            parse_event(b"\x1B[27;5;13~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Enter,
+                KeyModifiers::CONTROL,
+            )))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[27;6;65~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('A'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            )))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[27;2;9~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::BackTab,
+                KeyModifiers::SHIFT,
+            )))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[27;5;91~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('['),
+                KeyModifiers::CONTROL,
+            )))),
+        );
+        assert!(parse_event(b"\x1B[27;5~", false).is_err());
+
+        // With `formatOtherKeys` set to 1.
+        assert_eq!(
+            parse_event(b"\x1B[65;6u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+            )))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[13;5u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Enter,
+                KeyModifiers::CONTROL,
+            )))),
+        );
+    }
+
     #[test]
     fn test_parse_csi_special_key_code_with_types() {
         assert_eq!(
@@ -14,6 +14,7 @@ use winapi::um::{
 
 use crate::event::{
     Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    MousePositionUnit,
 };
 
 #[derive(Default)]
@@ -374,5 +375,6 @@ fn parse_mouse_event_record(
         column: xpos,
         row: ypos,
         modifiers,
+        position_unit: MousePositionUnit::Cell,
     }))
 }
@@ -100,10 +100,33 @@ pub(crate) mod sys;
 
 #[cfg(feature = "events")]
 pub use sys::{
-    query_keyboard_enhancement_flags, query_terminal_theme_mode, supports_keyboard_enhancement,
-    supports_synchronized_output, terminal_features,
+    query, query_background_color, query_cursor_color, query_foreground_color,
+    query_keyboard_enhancement_flags, query_palette, query_terminal_identity,
+    query_terminal_theme_mode, supports_keyboard_enhancement, supports_synchronized_output,
+    terminal_features,
 };
 
+#[cfg(feature = "events")]
+static QUERY_TIMEOUT: parking_lot::Mutex<std::time::Duration> =
+    parking_lot::const_mutex(std::time::Duration::from_millis(2000));
+
+/// Sets how long to wait for the terminal to respond to the queries of functions like
+/// [`terminal_features`] or [`cursor::position`](crate::cursor::position). The default is 2
+/// seconds.
+///
+/// A terminal which doesn't support a query usually answers the primary device attributes query
+/// sent after it right away, so the timeout is only reached if the terminal doesn't answer at all,
+/// for example over a slow connection.
+#[cfg(feature = "events")]
+pub fn set_query_timeout(timeout: std::time::Duration) {
+    *QUERY_TIMEOUT.lock() = timeout;
+}
+
+#[cfg(all(unix, feature = "events"))]
+pub(crate) fn query_timeout() -> std::time::Duration {
+    *QUERY_TIMEOUT.lock()
+}
+
 /// Tells whether the raw mode is enabled.
 ///
 /// Please have a look at the [raw mode](./index.html#raw-mode) section.
@@ -140,7 +163,8 @@ pub fn size() -> io::Result<(u16, u16)> {
     sys::size()
 }
 
-#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowSize {
     pub rows: u16,
     pub columns: u16,
@@ -157,6 +181,74 @@ pub fn window_size() -> io::Result<WindowSize> {
     sys::window_size()
 }
 
+/// Suspends the process like `Ctrl+Z` does in a shell, handing the terminal back to the shell
+/// until the process is continued.
+///
+/// `leave` is called to leave the modes the application entered, such as the alternate screen or
+/// mouse capture, and raw mode is disabled before the process stops itself with `SIGTSTP`. Once
+/// the process is continued with `SIGCONT`, for example by `fg`, raw mode is enabled again if it
+/// was enabled and `enter` is called to enter the modes again. This function returns after that,
+/// and the application should redraw the screen. This registers `SIGCONT` with
+/// [`register_signals`](crate::event::register_signals), so an
+/// [`Event::Resumed`](crate::event::Event::Resumed) is reported as well.
+///
+/// In raw mode, `Ctrl+Z` is reported as a key event instead of suspending the process, so this can
+/// be called when it is pressed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::stdout;
+///
+/// use crossterm::event::{
+///     read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+/// };
+/// use crossterm::execute;
+/// use crossterm::terminal::{suspend, EnterAlternateScreen, LeaveAlternateScreen};
+///
+/// let mut stdout = stdout();
+/// loop {
+///     match read()? {
+///         Event::Key(KeyEvent {
+///             code: KeyCode::Char('z'),
+///             modifiers: KeyModifiers::CONTROL,
+///             ..
+///         }) => suspend(
+///             &mut stdout,
+///             |w| execute!(w, DisableMouseCapture, LeaveAlternateScreen),
+///             |w| execute!(w, EnterAlternateScreen, EnableMouseCapture),
+///         )?,
+///         Event::Resumed => println!("Redraw"),
+///         _ => {}
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(all(unix, feature = "events"))]
+pub fn suspend<W, L, E>(writer: &mut W, leave: L, enter: E) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    L: FnOnce(&mut W) -> io::Result<()>,
+    E: FnOnce(&mut W) -> io::Result<()>,
+{
+    // Report the continuation as an event.
+    crate::event::register_signals(&[signal_hook::consts::SIGCONT])?;
+
+    leave(writer)?;
+    let raw_mode = sys::is_raw_mode_enabled();
+    if raw_mode {
+        sys::disable_raw_mode()?;
+    }
+
+    signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)?;
+
+    // The process has been continued.
+    if raw_mode {
+        sys::enable_raw_mode()?;
+    }
+    enter(writer)
+}
+
 /// Disables line wrapping.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DisableLineWrap;
@@ -7,8 +7,10 @@ pub(crate) use self::unix::{
 #[cfg(unix)]
 #[cfg(feature = "events")]
 pub use self::unix::{
-    query_keyboard_enhancement_flags, query_terminal_theme_mode, supports_keyboard_enhancement,
-    supports_synchronized_output, terminal_features,
+    query, query_background_color, query_cursor_color, query_foreground_color,
+    query_keyboard_enhancement_flags, query_palette, query_terminal_identity,
+    query_terminal_theme_mode, supports_keyboard_enhancement, supports_synchronized_output,
+    terminal_features,
 };
 #[cfg(all(windows, test))]
 pub(crate) use self::windows::temp_screen_buffer;
@@ -20,8 +22,10 @@ pub(crate) use self::windows::{
 #[cfg(windows)]
 #[cfg(feature = "events")]
 pub use self::windows::{
-    query_keyboard_enhancement_flags, query_terminal_theme_mode, supports_keyboard_enhancement,
-    supports_synchronized_output, terminal_features,
+    query, query_background_color, query_cursor_color, query_foreground_color,
+    query_keyboard_enhancement_flags, query_palette, query_terminal_identity,
+    query_terminal_theme_mode, supports_keyboard_enhancement, supports_synchronized_output,
+    terminal_features,
 };
 
 #[cfg(windows)]
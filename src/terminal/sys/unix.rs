@@ -1,11 +1,22 @@
 //! UNIX related logic for terminal manipulation.
 
 #[cfg(feature = "events")]
-use crate::event::{KeyboardEnhancementFlags, TerminalFeatures, ThemeMode};
+use crate::event::{
+    IdentityText, KeyboardEnhancementFlags, QueryResponse, TerminalFeatures, TerminalIdentity,
+    ThemeMode,
+};
 use crate::terminal::{
     sys::file_descriptor::{tty_fd, FileDesc},
     WindowSize,
 };
+#[cfg(feature = "events")]
+use crate::{
+    event::sys::unix::parse::{
+        parse_osc_color_response, parse_osc_palette_response, parse_xtversion_response,
+    },
+    style::Color,
+    terminal::query_timeout,
+};
 #[cfg(feature = "libc")]
 use libc::{
     cfmakeraw, ioctl, tcgetattr, tcsetattr, termios as Termios, winsize, STDOUT_FILENO, TCSANOW,
@@ -18,6 +29,8 @@ use rustix::{
     termios::{Termios, Winsize},
 };
 
+#[cfg(feature = "events")]
+use std::{collections::BTreeMap, ops::RangeInclusive};
 use std::{fs::File, io, process};
 #[cfg(feature = "libc")]
 use std::{
@@ -67,7 +80,7 @@ pub(crate) fn window_size() -> io::Result<WindowSize> {
         ws_ypixel: 0,
     };
 
-    let file = File::open("/dev/tty").map(|file| (FileDesc::new(file.into_raw_fd(), true)));
+    let file = File::open("/dev/tty").map(|file| FileDesc::new(file.into_raw_fd(), true));
     let fd = if let Ok(file) = &file {
         file.raw_fd()
     } else {
@@ -84,7 +97,7 @@ pub(crate) fn window_size() -> io::Result<WindowSize> {
 
 #[cfg(not(feature = "libc"))]
 pub(crate) fn window_size() -> io::Result<WindowSize> {
-    let file = File::open("/dev/tty").map(|file| (FileDesc::Owned(file.into())));
+    let file = File::open("/dev/tty").map(|file| FileDesc::Owned(file.into()));
     let fd = if let Ok(file) = &file {
         file.as_fd()
     } else {
@@ -180,296 +193,435 @@ fn set_terminal_attr(fd: impl AsFd, termios: &Termios) -> io::Result<()> {
     Ok(())
 }
 
-/// Queries the currently selected theme mode (dark/light) from the terminal.
+/// Writes `request` followed by a primary device attributes query, which all terminals answer,
+/// and passes the responses accepted by `filter` to `matcher`.
+///
+/// `filter` must accept `InternalEvent::PrimaryDeviceAttributes`. With `capture_responses`,
+/// control sequences crossterm doesn't parse are read as `InternalEvent::ControlSequence`.
 ///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+/// Returns the first value `matcher` returns, `None` if the terminal answered only the device
+/// attributes, or a timeout error.
 #[cfg(feature = "events")]
-pub fn query_terminal_theme_mode() -> io::Result<Option<ThemeMode>> {
-    if is_raw_mode_enabled() {
-        query_terminal_theme_mode_raw()
+fn query_with_sentinel<F, T, M>(
+    request: &[u8],
+    filter: F,
+    capture_responses: bool,
+    timeout: std::time::Duration,
+    matcher: M,
+) -> io::Result<Option<T>>
+where
+    F: crate::event::filter::Filter,
+    M: FnMut(crate::event::InternalEvent) -> Option<T>,
+{
+    let send = || crate::event::query::send(request, filter, true, capture_responses, timeout);
+    let responses = if is_raw_mode_enabled() {
+        send()?
     } else {
-        query_terminal_theme_mode_nonraw()
-    }
+        enable_raw_mode()?;
+        let responses = send();
+        disable_raw_mode()?;
+        responses?
+    };
+    Ok(responses.into_iter().find_map(matcher))
 }
 
+/// Sends a query to the terminal and returns the response `matcher` picks from its responses.
+///
+/// `request` is written to the terminal followed by a primary device attributes query
+/// (`ESC [ c`), which all terminals answer. Every response read until the device attributes
+/// arrive is passed to `matcher`, and the first value it returns is returned. If the terminal
+/// only answered the device attributes, it doesn't support the query and `Ok(None)` is returned.
+/// If the terminal doesn't answer within `timeout`, an error of kind
+/// [`ErrorKind::TimedOut`](io::ErrorKind::TimedOut) is returned.
+///
+/// Responses crossterm parses itself, such as the cursor position, aren't passed to `matcher`.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
+///
+/// # Examples
+///
+/// Query whether bracketed paste (mode 2004) is enabled with DECRQM:
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use crossterm::event::QueryResponse;
+/// use crossterm::terminal::query;
+///
+/// // ESC [ ? 2004 ; <state> $ y
+/// let state = query(b"\x1B[?2004$p", Duration::from_millis(500), |response| {
+///     match response {
+///         QueryResponse::Csi(csi) if csi.final_byte == b'y' && csi.parameters[0] == [2004] => {
+///             csi.parameters.get(1).map(|state| state[0])
+///         }
+///         _ => None,
+///     }
+/// })?;
+/// println!("Bracketed paste enabled: {}", state == Some(1));
+/// # std::io::Result::Ok(())
+/// ```
 #[cfg(feature = "events")]
-fn query_terminal_theme_mode_nonraw() -> io::Result<Option<ThemeMode>> {
-    enable_raw_mode()?;
-    let theme_mode = query_terminal_theme_mode_raw();
-    disable_raw_mode()?;
-    theme_mode
+pub fn query<T, M>(
+    request: &[u8],
+    timeout: std::time::Duration,
+    mut matcher: M,
+) -> io::Result<Option<T>>
+where
+    M: FnMut(QueryResponse<'_>) -> Option<T>,
+{
+    use crate::event::{filter::QueryResponseFilter, InternalEvent};
+
+    query_with_sentinel(
+        request,
+        QueryResponseFilter,
+        true,
+        timeout,
+        |response| match response {
+            InternalEvent::ControlSequence(csi) => matcher(QueryResponse::Csi(&csi)),
+            InternalEvent::OperatingSystemCommand(data) => matcher(QueryResponse::Osc(&data)),
+            InternalEvent::DeviceControlString(data) => matcher(QueryResponse::Dcs(&data)),
+            InternalEvent::ApplicationProgramCommand(data) => matcher(QueryResponse::Apc(&data)),
+            _ => None,
+        },
+    )
 }
 
+/// Queries the currently selected theme mode (dark/light) from the terminal.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-fn query_terminal_theme_mode_raw() -> io::Result<Option<ThemeMode>> {
-    use crate::event::{
-        filter::{PrimaryDeviceAttributesFilter, ThemeModeFilter},
-        poll_internal, read_internal, Event, InternalEvent,
-    };
-    use std::io::Write;
-    use std::time::Duration;
+pub fn query_terminal_theme_mode() -> io::Result<Option<ThemeMode>> {
+    use crate::event::{filter::ThemeModeFilter, Event, InternalEvent};
 
     // ESC [ ? 996 n         Query current terminal theme mode
-    // ESC [ c               Query primary device attributes (widely supported)
-    const QUERY: &[u8] = b"\x1B[?996n\x1B[c";
-
-    let result = File::open("/dev/tty").and_then(|mut file| {
-        file.write_all(QUERY)?;
-        file.flush()
-    });
-    if result.is_err() {
-        let mut stdout = io::stdout();
-        stdout.write_all(QUERY)?;
-        stdout.flush()?;
-    }
-
-    loop {
-        match poll_internal(Some(Duration::from_millis(2000)), &ThemeModeFilter) {
-            Ok(true) => match read_internal(&ThemeModeFilter) {
-                Ok(InternalEvent::Event(Event::ThemeModeChanged(theme_mode))) => {
-                    // Flush the PrimaryDeviceAttributes out of the event queue.
-                    read_internal(&PrimaryDeviceAttributesFilter).ok();
-                    return Ok(Some(theme_mode));
-                }
-                _ => return Ok(None),
-            },
-            Ok(false) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "The theme mode could not be read in a normal duration",
-                ));
-            }
-            Err(_) => {}
-        }
-    }
+    query_with_sentinel(
+        b"\x1B[?996n",
+        ThemeModeFilter,
+        false,
+        query_timeout(),
+        |response| match response {
+            InternalEvent::Event(Event::ThemeModeChanged(theme_mode)) => Some(theme_mode),
+            _ => None,
+        },
+    )
 }
 
+/// Queries the terminal's support for synchronized output sequences.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-fn supports_synchronized_output_raw() -> io::Result<bool> {
+pub fn supports_synchronized_output() -> io::Result<bool> {
     use crate::event::{
-        filter::{PrimaryDeviceAttributesFilter, SynchronizedOutputModeFilter},
-        poll_internal, read_internal, InternalEvent, SynchronizedOutputMode,
+        filter::SynchronizedOutputModeFilter, InternalEvent, SynchronizedOutputMode,
     };
-    use std::io::Write;
-    use std::time::Duration;
 
     // ESC [ ? 2026 $ p      DECRQM request for synchronized output state
-    // ESC [ c               Query primary device attributes (widely supported)
-    const QUERY: &[u8] = b"\x1B[?2026$p\x1B[c";
-
-    let result = File::open("/dev/tty").and_then(|mut file| {
-        file.write_all(QUERY)?;
-        file.flush()
-    });
-    if result.is_err() {
-        let mut stdout = io::stdout();
-        stdout.write_all(QUERY)?;
-        stdout.flush()?;
-    }
-
-    loop {
-        match poll_internal(
-            Some(Duration::from_millis(2000)),
-            &SynchronizedOutputModeFilter,
-        ) {
-            Ok(true) => match read_internal(&SynchronizedOutputModeFilter) {
-                Ok(InternalEvent::SynchronizedOutputMode(
-                    SynchronizedOutputMode::Set | SynchronizedOutputMode::Reset,
-                )) => {
-                    // Flush the PrimaryDeviceAttributes out of the event queue.
-                    read_internal(&PrimaryDeviceAttributesFilter).ok();
-                    return Ok(true);
-                }
-                _ => return Ok(false),
-            },
-            Ok(false) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Synchronized output mode could not be read in a normal duration",
-                ));
-            }
-            Err(_) => {}
-        }
-    }
+    let mode = query_with_sentinel(
+        b"\x1B[?2026$p",
+        SynchronizedOutputModeFilter,
+        false,
+        query_timeout(),
+        |response| match response {
+            InternalEvent::SynchronizedOutputMode(mode) => Some(mode),
+            _ => None,
+        },
+    )?;
+    Ok(matches!(
+        mode,
+        Some(SynchronizedOutputMode::Set | SynchronizedOutputMode::Reset)
+    ))
 }
 
+/// Queries the terminal's support for progressive keyboard enhancement.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-fn supports_synchronized_output_nonraw() -> io::Result<bool> {
-    enable_raw_mode()?;
-    let is_supported = supports_synchronized_output_raw();
-    disable_raw_mode()?;
-    is_supported
+pub fn supports_keyboard_enhancement() -> io::Result<bool> {
+    query_keyboard_enhancement_flags().map(|flags| flags.is_some())
 }
 
-/// Queries the terminal's support for synchronized output sequences.
+/// Queries the terminal's currently active keyboard enhancement flags.
 ///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-pub fn supports_synchronized_output() -> io::Result<bool> {
-    if is_raw_mode_enabled() {
-        supports_synchronized_output_raw()
-    } else {
-        supports_synchronized_output_nonraw()
-    }
+pub fn query_keyboard_enhancement_flags() -> io::Result<Option<KeyboardEnhancementFlags>> {
+    use crate::event::{filter::KeyboardEnhancementFlagsFilter, InternalEvent};
+
+    // This is the recommended method for testing support for the keyboard enhancement protocol.
+    // We send a query for the flags supported by the terminal and then the primary device attributes
+    // query. If we receive the primary device attributes response but not the keyboard enhancement
+    // flags, none of the flags are supported.
+    //
+    // See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/#detection-of-support-for-this-protocol>
+
+    // ESC [ ? u        Query progressive keyboard enhancement flags (kitty protocol).
+    query_with_sentinel(
+        b"\x1B[?u",
+        KeyboardEnhancementFlagsFilter,
+        false,
+        query_timeout(),
+        |response| match response {
+            InternalEvent::KeyboardEnhancementFlags(flags) => Some(flags),
+            _ => None,
+        },
+    )
 }
 
-/// Queries the terminal's support for progressive keyboard enhancement.
+/// Queries one of the dynamic colors, `10` for the foreground, `11` for the background and `12`
+/// for the cursor color.
+#[cfg(feature = "events")]
+fn query_dynamic_color(code: &[u8]) -> io::Result<Option<Color>> {
+    use crate::event::{filter::OperatingSystemCommandFilter, InternalEvent};
+
+    // ESC ] <code> ; ? ESC \      Query the dynamic color
+    let mut query = b"\x1B]".to_vec();
+    query.extend_from_slice(code);
+    query.extend_from_slice(b";?\x1B\\");
+
+    query_with_sentinel(
+        &query,
+        OperatingSystemCommandFilter,
+        false,
+        query_timeout(),
+        |response| match response {
+            InternalEvent::OperatingSystemCommand(data) => parse_osc_color_response(&data, code),
+            _ => None,
+        },
+    )
+}
+
+/// Queries the default foreground color of the terminal, `OSC 10`.
 ///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+/// Returns `Ok(None)` if the terminal doesn't support the query.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-pub fn supports_keyboard_enhancement() -> io::Result<bool> {
-    query_keyboard_enhancement_flags().map(|flags| flags.is_some())
+pub fn query_foreground_color() -> io::Result<Option<Color>> {
+    query_dynamic_color(b"10")
 }
 
-/// Queries the terminal's currently active keyboard enhancement flags.
+/// Queries the default background color of the terminal, `OSC 11`.
 ///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+/// Returns `Ok(None)` if the terminal doesn't support the query.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::{style::Color, terminal::query_background_color};
+///
+/// if let Some(Color::Rgb { r, g, b }) = query_background_color()? {
+///     let dark = u32::from(r) + u32::from(g) + u32::from(b) < 3 * 128;
+///     println!("The background is {}", if dark { "dark" } else { "light" });
+/// }
+/// # std::io::Result::Ok(())
+/// ```
 #[cfg(feature = "events")]
-pub fn query_keyboard_enhancement_flags() -> io::Result<Option<KeyboardEnhancementFlags>> {
-    if is_raw_mode_enabled() {
-        query_keyboard_enhancement_flags_raw()
-    } else {
-        query_keyboard_enhancement_flags_nonraw()
-    }
+pub fn query_background_color() -> io::Result<Option<Color>> {
+    query_dynamic_color(b"11")
 }
 
+/// Queries the color of the cursor, `OSC 12`.
+///
+/// Returns `Ok(None)` if the terminal doesn't support the query.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-fn query_keyboard_enhancement_flags_nonraw() -> io::Result<Option<KeyboardEnhancementFlags>> {
-    enable_raw_mode()?;
-    let flags = query_keyboard_enhancement_flags_raw();
-    disable_raw_mode()?;
-    flags
+pub fn query_cursor_color() -> io::Result<Option<Color>> {
+    query_dynamic_color(b"12")
 }
 
+/// Queries the colors of the palette entries in `indices`, `OSC 4`.
+///
+/// Returns the colors by their index, missing the entries the terminal didn't report. The map is
+/// empty if the terminal doesn't support the query.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::terminal::query_palette;
+///
+/// // The 16 named colors.
+/// for (index, color) in query_palette(0..=15)? {
+///     println!("{index}: {color:?}");
+/// }
+/// # std::io::Result::Ok(())
+/// ```
 #[cfg(feature = "events")]
-fn query_keyboard_enhancement_flags_raw() -> io::Result<Option<KeyboardEnhancementFlags>> {
-    use crate::event::{
-        filter::{KeyboardEnhancementFlagsFilter, PrimaryDeviceAttributesFilter},
-        poll_internal, read_internal, InternalEvent,
-    };
+pub fn query_palette(indices: RangeInclusive<u8>) -> io::Result<BTreeMap<u8, Color>> {
     use std::io::Write;
-    use std::time::Duration;
 
-    // This is the recommended method for testing support for the keyboard enhancement protocol.
-    // We send a query for the flags supported by the terminal and then the primary device attributes
-    // query. If we receive the primary device attributes response but not the keyboard enhancement
-    // flags, none of the flags are supported.
-    //
-    // See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/#detection-of-support-for-this-protocol>
+    use crate::event::{filter::OperatingSystemCommandFilter, InternalEvent};
 
-    // ESC [ ? u        Query progressive keyboard enhancement flags (kitty protocol).
-    // ESC [ c          Query primary device attributes.
-    const QUERY: &[u8] = b"\x1B[?u\x1B[c";
-
-    let result = File::open("/dev/tty").and_then(|mut file| {
-        file.write_all(QUERY)?;
-        file.flush()
-    });
-    if result.is_err() {
-        let mut stdout = io::stdout();
-        stdout.write_all(QUERY)?;
-        stdout.flush()?;
+    // ESC ] 4 ; <index> ; ? ESC \      Query the color of the palette entry
+    let mut query = Vec::new();
+    for index in indices {
+        write!(query, "\x1B]4;{index};?\x1B\\")?;
     }
 
-    loop {
-        match poll_internal(
-            Some(Duration::from_millis(2000)),
-            &KeyboardEnhancementFlagsFilter,
-        ) {
-            Ok(true) => {
-                match read_internal(&KeyboardEnhancementFlagsFilter) {
-                    Ok(InternalEvent::KeyboardEnhancementFlags(current_flags)) => {
-                        // Flush the PrimaryDeviceAttributes out of the event queue.
-                        read_internal(&PrimaryDeviceAttributesFilter).ok();
-                        return Ok(Some(current_flags));
-                    }
-                    _ => return Ok(None),
-                }
+    let mut palette = BTreeMap::new();
+    query_with_sentinel(
+        &query,
+        OperatingSystemCommandFilter,
+        false,
+        query_timeout(),
+        |response| {
+            if let InternalEvent::OperatingSystemCommand(data) = response {
+                palette.extend(parse_osc_palette_response(&data));
             }
-            Ok(false) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "The keyboard enhancement status could not be read within a normal duration",
-                ));
+            // Keep collecting the responses for all entries.
+            None::<()>
+        },
+    )?;
+    Ok(palette)
+}
+
+/// Adds a response to the identity queries to the identity.
+#[cfg(feature = "events")]
+fn add_identity_response(identity: &mut TerminalIdentity, response: crate::event::InternalEvent) {
+    use crate::event::InternalEvent;
+
+    match response {
+        InternalEvent::DeviceControlString(data) => {
+            if let Some((name, version)) = parse_xtversion_response(&data) {
+                identity.name = Some(IdentityText::new(&name));
+                identity.version = version.as_deref().map(IdentityText::new);
             }
-            Err(_) => {}
         }
+        InternalEvent::SecondaryDeviceAttributes(attributes) => {
+            identity.secondary_device_attributes = Some(attributes);
+        }
+        _ => {}
     }
 }
 
-/// Queries information about features that the terminal supports.
-///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+/// Names the terminal by its secondary device attributes if it doesn't support XTVERSION.
 #[cfg(feature = "events")]
-pub fn terminal_features() -> io::Result<TerminalFeatures> {
-    if is_raw_mode_enabled() {
-        terminal_features_raw()
-    } else {
-        terminal_features_nonraw()
+fn complete_identity(identity: TerminalIdentity) -> TerminalIdentity {
+    match identity.secondary_device_attributes {
+        Some(attributes) if identity.name.is_none() => TerminalIdentity::from(attributes),
+        _ => identity,
     }
 }
 
+/// Queries the name and version of the terminal with XTVERSION (`CSI > q`) and its secondary
+/// device attributes (`CSI > c`).
+///
+/// If the terminal doesn't support XTVERSION, the name and version of terminals known by their
+/// secondary device attributes are reported, see [`TerminalIdentity::from`]. The fields of the
+/// identity are `None` if the terminal doesn't support the queries.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::terminal::query_terminal_identity;
+///
+/// let identity = query_terminal_identity()?;
+/// match (identity.name, identity.secondary_device_attributes) {
+///     (Some(name), _) => println!("{name} {}", identity.version.as_deref().unwrap_or("")),
+///     (None, Some(attributes)) => println!("Terminal type {}", attributes.terminal_type),
+///     (None, None) => println!("Unknown terminal"),
+/// }
+/// # std::io::Result::Ok(())
+/// ```
 #[cfg(feature = "events")]
-fn terminal_features_nonraw() -> io::Result<TerminalFeatures> {
-    enable_raw_mode()?;
-    let features = terminal_features_raw();
-    disable_raw_mode()?;
-    features
+pub fn query_terminal_identity() -> io::Result<TerminalIdentity> {
+    use crate::event::filter::TerminalIdentityFilter;
+
+    // ESC [ > q        Query the name and version of the terminal (XTVERSION)
+    // ESC [ > c        Query secondary device attributes
+    let mut identity = TerminalIdentity::default();
+    query_with_sentinel(
+        b"\x1B[>q\x1B[>c",
+        TerminalIdentityFilter,
+        false,
+        query_timeout(),
+        |response| {
+            add_identity_response(&mut identity, response);
+            // Keep collecting the responses to both queries.
+            None::<()>
+        },
+    )?;
+    Ok(complete_identity(identity))
 }
 
+/// Queries information about features that the terminal supports.
+///
+/// This function can be called while another thread reads the events with
+/// [`crossterm::event::read`](crate::event::read) or an `EventStream`. The responses of the
+/// terminal aren't reported as events.
 #[cfg(feature = "events")]
-fn terminal_features_raw() -> io::Result<TerminalFeatures> {
-    use crate::event::{
-        filter::TerminalFeaturesFilter, poll_internal, read_internal, Event, InternalEvent,
-    };
-    use std::io::Write;
-    use std::time::Duration;
+pub fn terminal_features() -> io::Result<TerminalFeatures> {
+    use crate::event::{filter::TerminalFeaturesFilter, Event, InternalEvent};
 
     // ESC [ ? u             Query progressive keyboard enhancement flags (kitty protocol).
     // ESC [ ? 2026 $ p      DECRQM request for synchronized output state
     // ESC [ ? 996 n         Query current terminal theme mode
-    // ESC [ c               Query primary device attributes.
-    const QUERY: &[u8] = b"\x1B[?u\x1B[?2026$p\x1B[?996n\x1B[c";
-
-    let result = File::open("/dev/tty").and_then(|mut file| {
-        file.write_all(QUERY)?;
-        file.flush()
-    });
-    if result.is_err() {
-        let mut stdout = io::stdout();
-        stdout.write_all(QUERY)?;
-        stdout.flush()?;
-    }
+    // ESC ] 1x ; ? ESC \     Query the foreground, background and cursor colors
+    // ESC [ > q             Query the name and version of the terminal (XTVERSION)
+    // ESC [ > c             Query secondary device attributes
+    const QUERY: &[u8] =
+        b"\x1B[?u\x1B[?2026$p\x1B[?996n\x1B]10;?\x1B\\\x1B]11;?\x1B\\\x1B]12;?\x1B\\\x1B[>q\x1B[>c";
 
     let mut features = TerminalFeatures::default();
-    loop {
-        match poll_internal(Some(Duration::from_millis(2000)), &TerminalFeaturesFilter) {
-            Ok(true) => match read_internal(&TerminalFeaturesFilter) {
-                Ok(InternalEvent::KeyboardEnhancementFlags(flags)) => {
+    query_with_sentinel(
+        QUERY,
+        TerminalFeaturesFilter,
+        false,
+        query_timeout(),
+        |response| {
+            match response {
+                InternalEvent::KeyboardEnhancementFlags(flags) => {
                     features.keyboard_enhancement_flags = Some(flags);
                 }
-                Ok(InternalEvent::SynchronizedOutputMode(mode)) => {
+                InternalEvent::SynchronizedOutputMode(mode) => {
                     features.synchronized_output_mode = mode;
                 }
-                Ok(InternalEvent::Event(Event::ThemeModeChanged(theme_mode))) => {
+                InternalEvent::Event(Event::ThemeModeChanged(theme_mode)) => {
                     features.theme_mode = Some(theme_mode);
                 }
-                _ => return Ok(features),
-            },
-            Ok(false) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Terminal information could not be read within a normal duration",
-                ));
+                InternalEvent::OperatingSystemCommand(data) => {
+                    if let Some(color) = parse_osc_color_response(&data, b"10") {
+                        features.foreground_color = Some(color);
+                    } else if let Some(color) = parse_osc_color_response(&data, b"11") {
+                        features.background_color = Some(color);
+                    } else if let Some(color) = parse_osc_color_response(&data, b"12") {
+                        features.cursor_color = Some(color);
+                    }
+                }
+                response => add_identity_response(&mut features.identity, response),
             }
-            Err(_) => {}
-        }
-    }
+            // Keep collecting the responses to all queries.
+            None::<()>
+        },
+    )?;
+    features.identity = complete_identity(features.identity);
+    Ok(features)
 }
 
 /// execute tput with the given argument and parse
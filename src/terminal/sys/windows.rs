@@ -10,7 +10,11 @@ use winapi::{
 };
 
 #[cfg(feature = "events")]
-use crate::event::{KeyboardEnhancementFlags, TerminalFeatures, ThemeMode};
+use crate::event::{
+    KeyboardEnhancementFlags, QueryResponse, TerminalFeatures, TerminalIdentity, ThemeMode,
+};
+#[cfg(feature = "events")]
+use crate::style::Color;
 use crate::{
     cursor,
     terminal::{ClearType, WindowSize},
@@ -102,11 +106,68 @@ pub fn query_terminal_theme_mode() -> io::Result<Option<ThemeMode>> {
     Ok(None)
 }
 
+/// Queries the default foreground color of the terminal.
+///
+/// This always returns `Ok(None)` on Windows.
+#[cfg(feature = "events")]
+pub fn query_foreground_color() -> io::Result<Option<Color>> {
+    Ok(None)
+}
+
+/// Queries the default background color of the terminal.
+///
+/// This always returns `Ok(None)` on Windows.
+#[cfg(feature = "events")]
+pub fn query_background_color() -> io::Result<Option<Color>> {
+    Ok(None)
+}
+
+/// Queries the color of the cursor.
+///
+/// This always returns `Ok(None)` on Windows.
+#[cfg(feature = "events")]
+pub fn query_cursor_color() -> io::Result<Option<Color>> {
+    Ok(None)
+}
+
+/// Queries the colors of the palette entries in `indices`.
+///
+/// This always returns an empty map on Windows.
+#[cfg(feature = "events")]
+pub fn query_palette(
+    _indices: std::ops::RangeInclusive<u8>,
+) -> io::Result<std::collections::BTreeMap<u8, Color>> {
+    Ok(std::collections::BTreeMap::new())
+}
+
+/// Queries the name, version and secondary device attributes of the terminal.
+///
+/// This always returns an empty identity on Windows.
+#[cfg(feature = "events")]
+pub fn query_terminal_identity() -> io::Result<TerminalIdentity> {
+    Ok(TerminalIdentity::default())
+}
+
 #[cfg(feature = "events")]
 pub fn terminal_features() -> io::Result<TerminalFeatures> {
     Ok(TerminalFeatures::default())
 }
 
+/// Sends a query to the terminal and returns the response `matcher` picks from its responses.
+///
+/// This always returns `Ok(None)` on Windows.
+#[cfg(feature = "events")]
+pub fn query<T, M>(
+    _request: &[u8],
+    _timeout: std::time::Duration,
+    _matcher: M,
+) -> io::Result<Option<T>>
+where
+    M: FnMut(QueryResponse<'_>) -> Option<T>,
+{
+    Ok(None)
+}
+
 pub(crate) fn clear(clear_type: ClearType) -> std::io::Result<()> {
     let screen_buffer = ScreenBuffer::current()?;
     let csbi = screen_buffer.info()?;